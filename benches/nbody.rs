@@ -0,0 +1,100 @@
+use bevy::{input::InputPlugin, math::DVec3, prelude::*};
+use bevy_github_ci_template::{
+    barnes_hut::Octree,
+    benchmark::random_bodies,
+    physics::{accelerations_at, step_celestial_map, CelestialMap, Gravity},
+    Celestial, SimulationCommand, Universe, UniversePlugin, UniverseTick,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BODY_COUNTS: [usize; 4] = [10, 100, 500, 1000];
+const STEP: f32 = 0.016;
+
+fn bench_accelerations(c: &mut Criterion) {
+    let universe = Universe::default();
+    let mut group = c.benchmark_group("accelerations_at");
+    for count in BODY_COUNTS {
+        let bodies = random_bodies(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &bodies, |b, bodies| {
+            b.iter(|| accelerations_at(&universe, bodies))
+        });
+    }
+    group.finish();
+}
+
+fn bench_barnes_hut(c: &mut Criterion) {
+    let universe = Universe::default();
+    let gravity = Gravity {
+        gravitational_constant: universe.gravitational_constant as f64,
+        softening: universe.softening as f64,
+    };
+    let mut group = c.benchmark_group("barnes_hut");
+    for count in BODY_COUNTS {
+        let bodies = random_bodies(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &bodies, |b, bodies| {
+            b.iter(|| {
+                let octree = Octree::new(bodies);
+                (0..bodies.len())
+                    .map(|i| octree.acceleration(&gravity, 0.5, bodies, i))
+                    .collect::<Vec<DVec3>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_step(c: &mut Criterion) {
+    let universe = Universe::default();
+    let mut group = c.benchmark_group("step_celestial_map");
+    for count in BODY_COUNTS {
+        let mut world = World::new();
+        let map = random_bodies(count)
+            .into_iter()
+            .map(|body| (world.spawn().id(), body))
+            .collect();
+        let mut celestial_map = CelestialMap { map };
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| step_celestial_map(&mut celestial_map, &UniverseTick(STEP), &universe))
+        });
+    }
+    group.finish();
+}
+
+/// A full simulation tick through the ECS, without any rendering.
+fn bench_headless_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("headless_tick");
+    for count in BODY_COUNTS {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .add_plugin(UniversePlugin);
+        for body in random_bodies(count) {
+            app.world
+                .spawn()
+                .insert(Transform::from_translation(body.pos.as_vec3()))
+                .insert(Celestial::new(body.mass as f32, body.vel.as_vec3()));
+        }
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            // The universe stays paused, so each iteration runs exactly the
+            // one requested step.
+            b.iter(|| {
+                app.world.send_event(SimulationCommand::Step(1));
+                app.update();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_accelerations,
+    bench_barnes_hut,
+    bench_step,
+    bench_headless_tick
+);
+criterion_main!(benches);
@@ -0,0 +1,162 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+use bevy_mod_picking::Highlighting;
+
+use crate::{particles::TestParticle, Celestial, DebugMarker, Universe};
+
+/// The color a body had before `auto_color` took over, restored when it's
+/// switched off again.
+#[derive(Component)]
+struct AssignedColor(Color);
+
+/// The body's own material. Picking swaps the handle for shared highlight
+/// materials while hovered or selected, so prefer the one it stashed away.
+pub fn base_material<'a>(
+    material: &'a Handle<StandardMaterial>,
+    highlighting: Option<&'a Highlighting<StandardMaterial>>,
+) -> &'a Handle<StandardMaterial> {
+    highlighting.map_or(material, |highlighting| &highlighting.initial)
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    let [r, g, b, a] = from.lerp(to, t).to_array();
+    Color::rgba(r, g, b, a)
+}
+
+/// `t` from 0 to 1 along the low, mid, high gradient.
+fn gradient(universe: &Universe, t: f32) -> Color {
+    if t < 0.5 {
+        lerp_color(universe.color_low, universe.color_mid, t * 2.0)
+    } else {
+        lerp_color(universe.color_mid, universe.color_high, t * 2.0 - 1.0)
+    }
+}
+
+/// How trajectory lines, both predicted and past, are colored.
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineColoring {
+    /// The body's own color throughout.
+    #[default]
+    Body,
+    /// Along the gradient by speed, from the slowest to the fastest point of
+    /// each line.
+    Speed,
+    /// Along the gradient by how far in time each point is from now.
+    Age,
+}
+
+/// A color for each segment between consecutive `points`, which are evenly
+/// spaced in time. `present_first` says whether the first point is the
+/// present, as in a prediction, or the oldest, as in a trail.
+pub fn segment_colors(
+    universe: &Universe,
+    base: Color,
+    points: &[DVec3],
+    present_first: bool,
+) -> Vec<Color> {
+    let count = points.len().saturating_sub(1);
+    match universe.line_coloring {
+        LineColoring::Body => vec![base; count],
+        LineColoring::Speed => {
+            let speeds: Vec<f64> = points
+                .windows(2)
+                .map(|pair| pair[0].distance(pair[1]))
+                .collect();
+            let (min, max) = speeds
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(min, max), speed| {
+                    (min.min(*speed), max.max(*speed))
+                });
+            speeds
+                .iter()
+                .map(|speed| {
+                    let t = if max > min {
+                        (speed - min) / (max - min)
+                    } else {
+                        0.5
+                    };
+                    gradient(universe, t as f32)
+                })
+                .collect()
+        }
+        LineColoring::Age => (0..count)
+            .map(|i| {
+                let t = i as f32 / count.max(2).saturating_sub(1) as f32;
+                gradient(universe, if present_first { t } else { 1.0 - t })
+            })
+            .collect(),
+    }
+}
+
+fn mass_color(universe: &Universe, mass: f32, min_mass: f32, max_mass: f32) -> Color {
+    // Masses easily span several orders of magnitude, so spread them on a log scale.
+    let (mass, min_mass, max_mass) = (
+        mass.max(f32::MIN_POSITIVE).ln(),
+        min_mass.max(f32::MIN_POSITIVE).ln(),
+        max_mass.max(f32::MIN_POSITIVE).ln(),
+    );
+    let t = if max_mass > min_mass {
+        (mass - min_mass) / (max_mass - min_mass)
+    } else {
+        0.5
+    };
+    gradient(universe, t)
+}
+
+fn apply_mass_colors(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    bodies: Query<
+        (
+            Entity,
+            &Celestial,
+            &Handle<StandardMaterial>,
+            Option<&Highlighting<StandardMaterial>>,
+            Option<&AssignedColor>,
+        ),
+        (Without<DebugMarker>, Without<TestParticle>),
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !universe.auto_color {
+        for (entity, _, material, highlighting, assigned) in bodies.iter() {
+            if let Some(assigned) = assigned {
+                if let Some(material) = materials.get_mut(base_material(material, highlighting)) {
+                    material.base_color = assigned.0;
+                }
+                commands.entity(entity).remove::<AssignedColor>();
+            }
+        }
+        return;
+    }
+
+    let (min_mass, max_mass) = bodies
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), (_, body, ..)| {
+            (min.min(body.mass), max.max(body.mass))
+        });
+    for (entity, body, material, highlighting, assigned) in bodies.iter() {
+        let handle = base_material(material, highlighting);
+        let current = match materials.get(handle) {
+            Some(material) => material.base_color,
+            None => continue,
+        };
+        if assigned.is_none() {
+            commands.entity(entity).insert(AssignedColor(current));
+        }
+        let color = mass_color(&universe, body.mass, min_mass, max_mass);
+        if color != current {
+            materials.get_mut(handle).unwrap().base_color = color;
+        }
+    }
+}
+
+pub struct AppearancePlugin;
+
+impl Plugin for AppearancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_mass_colors);
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::FloatingOrigin,
+    overlay::{draw_wire_sphere, screen_position},
+    prediction::PredictedPath,
+    selection::PreviousSelection,
+    InspectTarget, Universe,
+};
+
+const APPROACH_COLOR: Color = Color::rgb(1.0, 1.0, 0.4);
+
+/// Step of the smallest separation between two predicted paths, with that
+/// separation. Paths made in the same refresh line up step for step.
+pub fn closest_approach(a: &PredictedPath, b: &PredictedPath) -> Option<(usize, f64)> {
+    a.positions
+        .iter()
+        .zip(&b.positions)
+        .map(|(a, b)| a.distance(*b))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Links the selected body and the one selected before it where their
+/// predictions come closest, labeled with the separation and when it happens.
+fn draw_closest_approach(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    inspector: Res<InspectTarget>,
+    previous: Res<PreviousSelection>,
+    mut lines: ResMut<DebugLines>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    paths: Query<&PredictedPath>,
+) {
+    if !universe.show_closest_approach {
+        return;
+    }
+    let (a, b) = match (inspector.target, previous.0) {
+        (Some(a), Some(b)) if a != b => match (paths.get(a), paths.get(b)) {
+            (Ok(a), Ok(b)) => (a, b),
+            _ => return,
+        },
+        _ => return,
+    };
+    let (index, distance) = match closest_approach(a, b) {
+        Some(closest) => closest,
+        None => return,
+    };
+    let (start, end) = match (a.points.get(index), b.points.get(index)) {
+        (Some(start), Some(end)) => (origin.to_render(*start), origin.to_render(*end)),
+        _ => return,
+    };
+    lines.line_colored(start, end, 0.0, APPROACH_COLOR);
+    let marker_radius = (distance as f32 * 0.05).clamp(0.2, 2.0);
+    draw_wire_sphere(&mut lines, start, marker_radius, APPROACH_COLOR);
+    draw_wire_sphere(&mut lines, end, marker_radius, APPROACH_COLOR);
+
+    let screen = camera
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| screen_position(camera, transform, start.lerp(end, 0.5)));
+    if let Some(screen) = screen {
+        let [r, g, b, _] = APPROACH_COLOR.as_rgba_f32();
+        egui_context
+            .ctx_mut()
+            .layer_painter(egui::LayerId::background())
+            .text(
+                screen + egui::vec2(6.0, -6.0),
+                Align2::LEFT_BOTTOM,
+                format!("{:.2} at t={:.2}", distance, a.time_at(index)),
+                FontId::proportional(12.0),
+                egui::Rgba::from_rgb(r, g, b).into(),
+            );
+    }
+}
+
+pub struct ClosestApproachPlugin;
+
+impl Plugin for ClosestApproachPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_closest_approach);
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::FloatingOrigin,
+    overlay::{draw_wire_sphere, screen_position},
+    prediction::PredictedPath,
+    Universe,
+};
+
+const PERIAPSIS_COLOR: Color = Color::rgb(0.3, 0.8, 1.0);
+const APOAPSIS_COLOR: Color = Color::rgb(1.0, 0.6, 0.2);
+const MARKER_RADIUS: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApsisKind {
+    Periapsis,
+    Apoapsis,
+}
+
+/// A closest or farthest point of a predicted path from the body it orbits.
+#[derive(Clone, Copy)]
+pub struct Apsis {
+    pub kind: ApsisKind,
+    /// Index into `PredictedPath::points`.
+    pub index: usize,
+    pub distance: f64,
+}
+
+/// Every local minimum and maximum of the distance between `body` and
+/// `attractor`, sampled at the same steps. The ends of the prediction don't
+/// count, since the path may carry on getting closer or farther past them.
+pub fn find_apsides(body: &[DVec3], attractor: &[DVec3]) -> Vec<Apsis> {
+    let distances: Vec<f64> = body
+        .iter()
+        .zip(attractor)
+        .map(|(body, attractor)| body.distance(*attractor))
+        .collect();
+    distances
+        .windows(3)
+        .enumerate()
+        .filter_map(|(i, window)| {
+            let kind = if window[0] > window[1] && window[1] <= window[2] {
+                ApsisKind::Periapsis
+            } else if window[0] < window[1] && window[1] >= window[2] {
+                ApsisKind::Apoapsis
+            } else {
+                return None;
+            };
+            Some(Apsis {
+                kind,
+                index: i + 1,
+                distance: window[1],
+            })
+        })
+        .collect()
+}
+
+fn draw_apsides(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut lines: ResMut<DebugLines>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    paths: Query<&PredictedPath>,
+) {
+    if !universe.show_apsides {
+        return;
+    }
+    let camera = camera.get_single().ok();
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for path in paths.iter() {
+        for apsis in path.apsides.iter() {
+            let point = match path.points.get(apsis.index) {
+                Some(point) => origin.to_render(*point),
+                None => continue,
+            };
+            let (color, label) = match apsis.kind {
+                ApsisKind::Periapsis => (PERIAPSIS_COLOR, "Pe"),
+                ApsisKind::Apoapsis => (APOAPSIS_COLOR, "Ap"),
+            };
+            draw_wire_sphere(&mut lines, point, MARKER_RADIUS, color);
+            let screen =
+                camera.and_then(|(camera, transform)| screen_position(camera, transform, point));
+            if let Some(screen) = screen {
+                let [r, g, b, _] = color.as_rgba_f32();
+                painter.text(
+                    screen + egui::vec2(6.0, -6.0),
+                    Align2::LEFT_BOTTOM,
+                    format!("{} {:.1}", label, apsis.distance),
+                    FontId::proportional(12.0),
+                    egui::Rgba::from_rgb(r, g, b).into(),
+                );
+            }
+        }
+    }
+}
+
+pub struct ApsidesPlugin;
+
+impl Plugin for ApsidesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_apsides);
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{physics::CelestialMap, Celestial, DebugMarker, InspectTarget};
+
+/// An exponential atmosphere around a body. Anything passing through it is
+/// slowed by drag, enough to aerobrake a spacecraft or bring down debris.
+#[derive(Component, Inspectable, Clone, Copy, Debug)]
+pub struct Atmosphere {
+    #[inspectable(min = 0.0, speed = 0.00001)]
+    pub surface_density: f32,
+    /// Height over which the density falls by a factor of e.
+    #[inspectable(min = 0.01)]
+    pub scale_height: f32,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Self {
+            surface_density: 0.0001,
+            scale_height: 2.0,
+        }
+    }
+}
+
+impl Atmosphere {
+    /// Density `altitude` above the surface. Below the surface it stays at
+    /// the surface density.
+    pub fn density_at(&self, altitude: f64) -> f64 {
+        self.surface_density as f64 * (-altitude.max(0.0) / self.scale_height as f64).exp()
+    }
+}
+
+/// Copies the atmospheres of a map built from the current world onto it.
+pub fn mark_atmospheres(celestial_map: &mut CelestialMap, atmospheres: &Query<&Atmosphere>) {
+    for (entity, bundle) in celestial_map.map.iter_mut() {
+        bundle.atmosphere = atmospheres.get(*entity).ok().copied();
+    }
+}
+
+/// Gives the selected body an atmosphere with K, or takes it away.
+fn toggle_atmosphere(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    bodies: Query<Option<&Atmosphere>, (With<Celestial>, Without<DebugMarker>)>,
+) {
+    if !keys.just_pressed(KeyCode::K) {
+        return;
+    }
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    match bodies.get(target) {
+        Ok(Some(_)) => {
+            commands.entity(target).remove::<Atmosphere>();
+        }
+        Ok(None) => {
+            commands.entity(target).insert(Atmosphere::default());
+        }
+        Err(_) => {}
+    }
+}
+
+pub struct AtmospherePlugin;
+
+impl Plugin for AtmospherePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<Atmosphere>()
+            .add_system(toggle_atmosphere);
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::math::DVec3;
+use bevy_inspector_egui::Inspectable;
+
+use crate::Universe;
+
+/// A fixed analytic potential centered on the simulation origin, felt by every
+/// body on top of their pull on each other. It stands in for a dominant mass
+/// that isn't worth simulating, like the bulk of a galaxy.
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackgroundField {
+    #[default]
+    None,
+    /// The same `background_acceleration` everywhere.
+    Uniform,
+    /// A point of `background_mass`, softened like the bodies are.
+    PointMass,
+    /// A Miyamoto-Nagai disk of `background_mass` lying in the XZ plane.
+    Disk,
+}
+
+/// Miyamoto-Nagai terms at `position`: `sqrt(z² + b²)` and
+/// `R² + (a + sqrt(z² + b²))²`, with the disk axis along Y.
+fn disk_terms(universe: &Universe, position: DVec3) -> (f64, f64) {
+    let scale_length = universe.disk_scale_length as f64;
+    let scale_height = universe.disk_scale_height as f64;
+    let vertical = (position.y * position.y + scale_height * scale_height).sqrt();
+    let cylindrical = position.x * position.x + position.z * position.z;
+    (vertical, cylindrical + (scale_length + vertical).powi(2))
+}
+
+pub fn background_acceleration(universe: &Universe, position: DVec3) -> DVec3 {
+    let gm = universe.gravitational_constant as f64 * universe.background_mass as f64;
+    match universe.background {
+        BackgroundField::None => DVec3::ZERO,
+        BackgroundField::Uniform => universe.background_acceleration.as_dvec3(),
+        BackgroundField::PointMass => {
+            let softening = universe.softening as f64;
+            let square_distance = position.length_squared() + softening * softening;
+            if square_distance == 0.0 {
+                return DVec3::ZERO;
+            }
+            -position * gm / (square_distance * square_distance.sqrt())
+        }
+        BackgroundField::Disk => {
+            let (vertical, square_distance) = disk_terms(universe, position);
+            let scale = gm / (square_distance * square_distance.sqrt());
+            let scale_length = universe.disk_scale_length as f64;
+            -DVec3::new(
+                position.x * scale,
+                position.y * scale * (scale_length + vertical) / vertical,
+                position.z * scale,
+            )
+        }
+    }
+}
+
+/// Potential per unit mass of the background at `position`, consistent with
+/// [`background_acceleration`].
+pub fn background_potential(universe: &Universe, position: DVec3) -> f64 {
+    let gm = universe.gravitational_constant as f64 * universe.background_mass as f64;
+    match universe.background {
+        BackgroundField::None => 0.0,
+        BackgroundField::Uniform => -universe.background_acceleration.as_dvec3().dot(position),
+        BackgroundField::PointMass => {
+            let softening = universe.softening as f64;
+            let distance = (position.length_squared() + softening * softening).sqrt();
+            if distance == 0.0 {
+                return 0.0;
+            }
+            -gm / distance
+        }
+        BackgroundField::Disk => -gm / disk_terms(universe, position).1.sqrt(),
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::math::DVec3;
+
+use crate::physics::{calculate_dt_velocity, CelestialBundle, Gravity};
+
+/// Coincident bodies would otherwise split forever.
+const MAX_DEPTH: usize = 32;
+
+struct Node {
+    center: DVec3,
+    half_size: f64,
+    mass: f64,
+    center_of_mass: DVec3,
+    children: Vec<usize>,
+    /// Bodies held directly by a leaf.
+    bodies: Vec<usize>,
+}
+
+impl Node {
+    fn contains(&self, point: DVec3) -> bool {
+        ((point - self.center)
+            .abs()
+            .cmple(DVec3::splat(self.half_size)))
+        .all()
+    }
+}
+
+/// An octree over the bodies' positions where every cell stores the total
+/// mass and center of mass of what it contains.
+pub struct Octree {
+    nodes: Vec<Node>,
+}
+
+impl Octree {
+    pub fn new(bodies: &[CelestialBundle]) -> Self {
+        let (min, max) = bodies.iter().fold(
+            (DVec3::splat(f64::MAX), DVec3::splat(f64::MIN)),
+            |(min, max), body| (min.min(body.pos), max.max(body.pos)),
+        );
+        let mut tree = Self { nodes: Vec::new() };
+        if !bodies.is_empty() {
+            let half_size = ((max - min).max_element() / 2.0).max(f64::EPSILON);
+            let indices = (0..bodies.len()).collect();
+            tree.build(bodies, indices, (min + max) / 2.0, half_size, 0);
+        }
+        tree
+    }
+
+    fn build(
+        &mut self,
+        bodies: &[CelestialBundle],
+        indices: Vec<usize>,
+        center: DVec3,
+        half_size: f64,
+        depth: usize,
+    ) -> usize {
+        let mass: f64 = indices.iter().map(|i| bodies[*i].mass).sum();
+        let weighted = indices
+            .iter()
+            .fold(DVec3::ZERO, |sum, i| sum + bodies[*i].pos * bodies[*i].mass);
+        let center_of_mass = if mass > 0.0 { weighted / mass } else { center };
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            center,
+            half_size,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            bodies: Vec::new(),
+        });
+        if indices.len() <= 1 || depth >= MAX_DEPTH {
+            self.nodes[index].bodies = indices;
+            return index;
+        }
+
+        let mut octants: [Vec<usize>; 8] = Default::default();
+        for i in indices {
+            let offset = bodies[i].pos - center;
+            let octant = (offset.x >= 0.0) as usize
+                | ((offset.y >= 0.0) as usize) << 1
+                | ((offset.z >= 0.0) as usize) << 2;
+            octants[octant].push(i);
+        }
+        let quarter = half_size / 2.0;
+        for (octant, members) in octants.into_iter().enumerate() {
+            if members.is_empty() {
+                continue;
+            }
+            let sign = |bit: usize| if octant & bit != 0 { 1.0 } else { -1.0 };
+            let child_center = center + DVec3::new(sign(1), sign(2), sign(4)) * quarter;
+            let child = self.build(bodies, members, child_center, quarter, depth + 1);
+            self.nodes[index].children.push(child);
+        }
+        index
+    }
+
+    /// Gravitational acceleration on `bodies[this]`. Cells that look smaller
+    /// than `theta` from the body are treated as a single point mass.
+    pub fn acceleration(
+        &self,
+        gravity: &Gravity,
+        theta: f64,
+        bodies: &[CelestialBundle],
+        this: usize,
+    ) -> DVec3 {
+        let position = bodies[this].pos;
+        let mut acceleration = DVec3::ZERO;
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.children.is_empty() {
+                for that in node.bodies.iter().filter(|that| **that != this) {
+                    acceleration += calculate_dt_velocity(
+                        gravity.gravitational_constant,
+                        gravity.softening,
+                        position,
+                        bodies[*that].pos,
+                        bodies[*that].mass,
+                    );
+                }
+                continue;
+            }
+            let distance = position.distance(node.center_of_mass);
+            if !node.contains(position) && 2.0 * node.half_size < theta * distance {
+                acceleration += calculate_dt_velocity(
+                    gravity.gravitational_constant,
+                    gravity.softening,
+                    position,
+                    node.center_of_mass,
+                    node.mass,
+                );
+            } else {
+                stack.extend(&node.children);
+            }
+        }
+        acceleration
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_mod_picking::PickableBundle;
+
+use crate::{
+    build_celestial_maps,
+    origin::{FloatingOrigin, SimState},
+    physics::CelestialMap,
+    step_universe, Celestial, DebugMarker, Name, Radius,
+};
+
+const MARKER_COLOR: Color = Color::rgb(1.0, 1.0, 0.6);
+
+/// Sits on the mass-weighted center of every body. It has no `Celestial`, so
+/// the simulation never sees it, but it can be picked and focused like a body.
+/// It outlives scenario reloads and is hidden while there is nothing to weigh.
+#[derive(Component)]
+pub struct Barycenter;
+
+/// Mass-weighted mean position of the bodies in the map, or `None` if they
+/// have no mass between them.
+pub fn system_barycenter(celestial_map: &CelestialMap) -> Option<DVec3> {
+    let (weighted_position, total_mass) = celestial_map
+        .map
+        .values()
+        .fold((DVec3::ZERO, 0.0), |(position, mass), body| {
+            (position + body.pos * body.mass, mass + body.mass)
+        });
+    (total_mass > 0.0).then(|| weighted_position / total_mass)
+}
+
+fn spawn_barycenter(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 1.0,
+                subdivisions: 0,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: MARKER_COLOR,
+                unlit: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .insert(Name {
+            name: "Barycenter".to_string(),
+        })
+        .insert(Radius(1.0))
+        .insert(Barycenter)
+        .insert_bundle(PickableBundle::default());
+}
+
+fn update_barycenter(
+    origin: Res<FloatingOrigin>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    mut marker: Query<(&mut Transform, &mut Visibility), (With<Barycenter>, Without<Celestial>)>,
+) {
+    let (mut transform, mut visibility) = match marker.get_single_mut() {
+        Ok(marker) => marker,
+        Err(_) => return,
+    };
+    match system_barycenter(&build_celestial_maps(&bodies)) {
+        Some(barycenter) => {
+            transform.translation = origin.to_render(barycenter);
+            visibility.is_visible = true;
+        }
+        None => visibility.is_visible = false,
+    }
+}
+
+pub struct BarycenterPlugin;
+
+impl Plugin for BarycenterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_barycenter)
+            .add_system(update_barycenter.after(step_universe));
+    }
+}
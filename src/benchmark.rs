@@ -0,0 +1,96 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use bevy::{math::DVec3, prelude::*};
+
+use crate::{
+    physics::{accelerations_at, step_celestial_map, CelestialBundle, CelestialMap},
+    Universe,
+};
+
+/// `count` bodies scattered through a cube, seeded by the count so every run
+/// benchmarks the same configuration. Shared with the criterion benches.
+pub fn random_bodies(count: usize) -> Vec<CelestialBundle> {
+    let rng = fastrand::Rng::with_seed(count as u64);
+    let coordinate = || DVec3::new(rng.f64(), rng.f64(), rng.f64()) * 2.0 - DVec3::ONE;
+    (0..count)
+        .map(|_| CelestialBundle {
+            pos: coordinate() * 500.0,
+            vel: coordinate() * 5.0,
+            acc: DVec3::ZERO,
+            mass: 1.0 + rng.f64() * 1000.0,
+            charge: 0.0,
+            pinned: false,
+            radius: 1.0,
+            atmosphere: None,
+        })
+        .collect()
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+fn write_timings(writer: &mut impl Write, label: &str, timings: &mut [Duration]) -> io::Result<()> {
+    timings.sort_unstable();
+    let micros = |duration: Duration| duration.as_secs_f64() * 1e6;
+    writeln!(
+        writer,
+        "{},{:.1},{:.1},{:.1},{:.1}",
+        label,
+        micros(percentile(timings, 0.5)),
+        micros(percentile(timings, 0.9)),
+        micros(percentile(timings, 0.99)),
+        micros(timings[timings.len() - 1]),
+    )
+}
+
+/// Steps `count` random bodies `steps` times, timing one force evaluation and
+/// the whole step separately each tick. The step includes however many force
+/// evaluations the integrator makes, so the difference is the integrator's own
+/// overhead. Timings are written in microseconds.
+pub fn run_benchmark(
+    universe: &Universe,
+    count: usize,
+    steps: u64,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let mut world = World::new();
+    let mut celestial_map = CelestialMap {
+        map: random_bodies(count)
+            .into_iter()
+            .map(|body| (world.spawn().id(), body))
+            .collect(),
+    };
+    let tick = universe.tick();
+    let steps = steps.max(1);
+    let mut forces = Vec::with_capacity(steps as usize);
+    let mut step = Vec::with_capacity(steps as usize);
+    for _ in 0..steps {
+        let bodies: Vec<CelestialBundle> = celestial_map
+            .entities(universe.deterministic)
+            .iter()
+            .map(|entity| celestial_map.map[entity])
+            .collect();
+        let start = Instant::now();
+        accelerations_at(universe, &bodies);
+        forces.push(start.elapsed());
+
+        let start = Instant::now();
+        step_celestial_map(&mut celestial_map, &tick, universe);
+        step.push(start.elapsed());
+    }
+
+    writeln!(
+        writer,
+        "# {} bodies, {} steps, {:?} integrator, barnes-hut {}",
+        count, steps, universe.integrator, universe.barnes_hut
+    )?;
+    writeln!(writer, "phase,p50_us,p90_us,p99_us,max_us")?;
+    write_timings(writer, "forces", &mut forces)?;
+    write_timings(writer, "step", &mut step)?;
+    writer.flush()
+}
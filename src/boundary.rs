@@ -0,0 +1,164 @@
+use bevy::{ecs::schedule::RunCriteria, math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    drag::Held,
+    origin::{FloatingOrigin, SimState},
+    overlay::draw_wire_sphere,
+    pin::Pinned,
+    recording::not_replaying,
+    step_universe, Celestial, DebugMarker, Radius, Universe, UniverseStep,
+};
+
+const BOUNDARY_COLOR: Color = Color::rgb(0.9, 0.4, 0.1);
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Bodies can fly off forever.
+    #[default]
+    None,
+    /// A sphere of `boundary_radius` around the simulation origin.
+    Sphere,
+    /// A horizontal floor at `floor_height`.
+    Floor,
+}
+
+/// Reflects `velocity` off a surface with the given inward `normal` if it is
+/// heading out through it, scaling the normal component by `restitution`.
+fn reflect(velocity: DVec3, normal: DVec3, restitution: f64) -> DVec3 {
+    let normal_speed = velocity.dot(normal);
+    if normal_speed < 0.0 {
+        velocity - normal * normal_speed * (1.0 + restitution)
+    } else {
+        velocity
+    }
+}
+
+/// Runs after every simulation step, in simulation space, where the boundary
+/// is centered on the origin.
+fn contain_bodies(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut bodies: Query<
+        (&mut Celestial, &mut SimState, &mut Transform, &Radius),
+        (Without<DebugMarker>, Without<Held>, Without<Pinned>),
+    >,
+) {
+    for (mut body, mut state, mut transform, radius) in bodies.iter_mut() {
+        let radius = radius.0 as f64;
+        let position = state.position;
+        // The inward surface normal and the point the body is pushed back to.
+        let (normal, inside) = match universe.boundary {
+            Boundary::None => return,
+            Boundary::Sphere => {
+                let limit = (universe.boundary_radius as f64 - radius).max(0.0);
+                if position.length() <= limit {
+                    continue;
+                }
+                let outward = position.try_normalize().unwrap_or(DVec3::X);
+                (-outward, outward * limit)
+            }
+            Boundary::Floor => {
+                let limit = universe.floor_height as f64 + radius;
+                if position.y >= limit {
+                    continue;
+                }
+                (DVec3::Y, DVec3::new(position.x, limit, position.z))
+            }
+        };
+        state.velocity = reflect(state.velocity, normal, universe.boundary_restitution as f64);
+        state.position = inside;
+        body.velocity = state.velocity.as_vec3();
+        transform.translation = origin.to_render(inside);
+    }
+}
+
+fn draw_boundary(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !universe.show_boundary {
+        return;
+    }
+    let center = origin.to_render(DVec3::ZERO);
+    match universe.boundary {
+        Boundary::None => {}
+        Boundary::Sphere => {
+            draw_wire_sphere(&mut lines, center, universe.boundary_radius, BOUNDARY_COLOR);
+        }
+        Boundary::Floor => {
+            let extent = universe.grid_extent;
+            let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .map(|(x, z)| center + Vec3::new(x * extent, universe.floor_height, z * extent));
+            for i in 0..corners.len() {
+                let next = corners[(i + 1) % corners.len()];
+                lines.line_colored(corners[i], next, 0.0, BOUNDARY_COLOR);
+            }
+            lines.line_colored(corners[0], corners[2], 0.0, BOUNDARY_COLOR);
+            lines.line_colored(corners[1], corners[3], 0.0, BOUNDARY_COLOR);
+        }
+    }
+}
+
+pub struct BoundaryPlugin;
+
+impl Plugin for BoundaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            contain_bodies
+                .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                .after(step_universe),
+        )
+        .add_system(draw_boundary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outgoing_bodies_bounce_back_in() {
+        let mut world = World::new();
+        world.insert_resource(Universe {
+            boundary: Boundary::Sphere,
+            boundary_radius: 300.0,
+            boundary_restitution: 0.5,
+            ..default()
+        });
+        world.init_resource::<FloatingOrigin>();
+        let body = world
+            .spawn()
+            .insert(Celestial::new(1.0, Vec3::X * 10.0))
+            .insert(SimState {
+                position: DVec3::X * 305.0,
+                velocity: DVec3::X * 10.0,
+                acceleration: DVec3::ZERO,
+            })
+            .insert(Transform::from_xyz(305.0, 0.0, 0.0))
+            .insert(Radius(1.0))
+            .id();
+        SystemStage::single(contain_bodies).run(&mut world);
+
+        let state = world.get::<SimState>(body).unwrap();
+        assert_eq!(state.velocity, DVec3::X * -5.0);
+        assert_eq!(state.position, DVec3::X * 299.0);
+
+        assert_eq!(
+            world.get::<Celestial>(body).unwrap().velocity,
+            Vec3::X * -5.0
+        );
+        assert_eq!(
+            world.get::<Transform>(body).unwrap().translation,
+            Vec3::X * 299.0
+        );
+    }
+
+    #[test]
+    fn incoming_bodies_keep_their_velocity() {
+        let velocity = DVec3::new(-3.0, 1.0, 0.0);
+        assert_eq!(reflect(velocity, -DVec3::X, 1.0), velocity);
+    }
+}
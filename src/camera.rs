@@ -0,0 +1,229 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::{input::mouse::MouseWheel, prelude::*, render::camera::Projection};
+use bevy_flycam::{FlyCam, MovementSettings};
+use bevy_inspector_egui::{bevy_egui::EguiContext, Inspectable};
+use bevy_mod_picking::PickingEvent;
+
+use crate::{barycenter::Barycenter, origin::OriginShifted, Celestial, DebugMarker, Radius};
+
+const DOUBLE_CLICK_WINDOW: f64 = 0.4;
+const FOCUS_DURATION: f32 = 0.5;
+const FOCUS_MARGIN: f32 = 3.0;
+const FIT_MARGIN: f32 = 1.2;
+const SPEED_NUDGE: f32 = 1.1;
+
+#[derive(Inspectable)]
+pub struct CameraSettings {
+    #[inspectable(min = 1.0, max = 10000.0)]
+    speed: f32,
+    #[inspectable(min = 0.00001, max = 0.001, speed = 0.00001)]
+    sensitivity: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            speed: 200.0,
+            sensitivity: 0.00012,
+        }
+    }
+}
+
+enum FocusTarget {
+    /// Follows a body, or the barycenter, while it keeps moving.
+    Body(Entity),
+    Point(Vec3),
+}
+
+struct FocusAnimation {
+    target: FocusTarget,
+    from: Vec3,
+    distance: f32,
+    elapsed: f32,
+}
+
+#[derive(Default)]
+pub struct CameraFocus {
+    animation: Option<FocusAnimation>,
+}
+
+/// Distance from a sphere of `radius` at which it fills the view for the given
+/// vertical field of view, padded by `margin`.
+pub fn framing_distance(radius: f32, fov: f32, margin: f32) -> f32 {
+    radius * margin / (fov / 2.0).sin()
+}
+
+pub fn camera_fov(projection: &Projection) -> f32 {
+    match projection {
+        Projection::Perspective(perspective) => perspective.fov,
+        Projection::Orthographic(_) => FRAC_PI_4,
+    }
+}
+
+pub fn focus_on_double_click(
+    mut events: EventReader<PickingEvent>,
+    time: Res<Time>,
+    mut last_click: Local<Option<(Entity, f64)>>,
+    mut focus: ResMut<CameraFocus>,
+    bodies: Query<&Radius, Or<(With<Celestial>, With<Barycenter>)>>,
+    camera: Query<(&Transform, &Projection), With<FlyCam>>,
+) {
+    for event in events.iter() {
+        let entity = match event {
+            PickingEvent::Clicked(entity) => *entity,
+            _ => continue,
+        };
+        let now = time.seconds_since_startup();
+        let is_double_click = matches!(
+            *last_click,
+            Some((last, at)) if last == entity && now - at <= DOUBLE_CLICK_WINDOW
+        );
+        if !is_double_click {
+            *last_click = Some((entity, now));
+            continue;
+        }
+        *last_click = None;
+
+        let (camera_transform, projection) = match camera.get_single() {
+            Ok(camera) => camera,
+            Err(_) => continue,
+        };
+        if let Ok(radius) = bodies.get(entity) {
+            focus.animation = Some(FocusAnimation {
+                target: FocusTarget::Body(entity),
+                from: camera_transform.translation,
+                distance: framing_distance(radius.0, camera_fov(projection), FOCUS_MARGIN),
+                elapsed: 0.0,
+            });
+        }
+    }
+}
+
+/// Frames every body: the camera keeps its view direction and backs off from
+/// the center of their bounding box until the whole box fits.
+fn fit_all_bodies(
+    keys: Res<Input<KeyCode>>,
+    mut focus: ResMut<CameraFocus>,
+    bodies: Query<(&Transform, &Radius), (With<Celestial>, Without<DebugMarker>, Without<FlyCam>)>,
+    camera: Query<(&Transform, &Projection), With<FlyCam>>,
+) {
+    if !keys.just_pressed(KeyCode::G) {
+        return;
+    }
+    let (camera_transform, projection) = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let (min, max) = bodies.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), (transform, _)| {
+            (
+                min.min(transform.translation),
+                max.max(transform.translation),
+            )
+        },
+    );
+    if min.x > max.x {
+        return;
+    }
+    let center = (min + max) / 2.0;
+    // A single body still has its own size to fit.
+    let radius = bodies
+        .iter()
+        .map(|(transform, radius)| transform.translation.distance(center) + radius.0)
+        .fold(0.0, f32::max);
+
+    focus.animation = Some(FocusAnimation {
+        target: FocusTarget::Point(center),
+        from: camera_transform.translation,
+        distance: framing_distance(radius, camera_fov(projection), FIT_MARGIN),
+        elapsed: 0.0,
+    });
+}
+
+pub fn animate_camera_focus(
+    time: Res<Time>,
+    mut focus: ResMut<CameraFocus>,
+    bodies: Query<&Transform, (Or<(With<Celestial>, With<Barycenter>)>, Without<FlyCam>)>,
+    mut camera: Query<&mut Transform, With<FlyCam>>,
+) {
+    let animation = match focus.animation.as_mut() {
+        Some(animation) => animation,
+        None => return,
+    };
+    let target = match animation.target {
+        FocusTarget::Body(entity) => bodies.get(entity).ok().map(|t| t.translation),
+        FocusTarget::Point(point) => Some(point),
+    };
+    let (target, mut camera_transform) = match (target, camera.get_single_mut()) {
+        (Some(target), Ok(camera_transform)) => (target, camera_transform),
+        _ => {
+            focus.animation = None;
+            return;
+        }
+    };
+
+    animation.elapsed += time.delta_seconds();
+    let t = (animation.elapsed / FOCUS_DURATION).min(1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    let destination = target - camera_transform.forward() * animation.distance;
+    camera_transform.translation = animation.from.lerp(destination, eased);
+
+    if t >= 1.0 {
+        focus.animation = None;
+    }
+}
+
+/// Scrolling over the scene scales the fly speed, so it can keep up with
+/// whatever scale the current scenario uses.
+fn nudge_camera_speed(
+    mut wheel: EventReader<MouseWheel>,
+    mut egui_context: ResMut<EguiContext>,
+    mut settings: ResMut<CameraSettings>,
+) {
+    let scroll: f32 = wheel.iter().map(|event| event.y.signum()).sum();
+    if scroll == 0.0 || egui_context.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    settings.speed = (settings.speed * SPEED_NUDGE.powf(scroll)).clamp(1.0, 10000.0);
+}
+
+/// Keeps an animation in flight pointed at the same place in the world when the
+/// render origin moves under it.
+fn follow_origin_shift(mut shifted: EventReader<OriginShifted>, mut focus: ResMut<CameraFocus>) {
+    for OriginShifted(offset) in shifted.iter() {
+        if let Some(animation) = focus.animation.as_mut() {
+            animation.from -= *offset;
+            if let FocusTarget::Point(point) = &mut animation.target {
+                *point -= *offset;
+            }
+        }
+    }
+}
+
+fn apply_camera_settings(settings: Res<CameraSettings>, mut movement: ResMut<MovementSettings>) {
+    if settings.is_changed() {
+        movement.speed = settings.speed;
+        movement.sensitivity = settings.sensitivity;
+    }
+}
+
+pub struct CameraControlPlugin;
+
+impl Plugin for CameraControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFocus>()
+            .init_resource::<CameraSettings>()
+            .add_system(nudge_camera_speed)
+            .add_system(apply_camera_settings.after(nudge_camera_speed))
+            .add_system(focus_on_double_click)
+            .add_system(fit_all_bodies)
+            .add_system(follow_origin_shift.before(animate_camera_focus))
+            .add_system(
+                animate_camera_focus
+                    .after(focus_on_double_click)
+                    .after(fit_all_bodies),
+            );
+    }
+}
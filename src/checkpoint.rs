@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+use bevy_mod_picking::Highlighting;
+
+use crate::{
+    appearance::base_material,
+    atmosphere::Atmosphere,
+    frames::ParentBody,
+    origin::{FloatingOrigin, SimState},
+    particles::{spawn_tracer, tracer_assets, TestParticle},
+    pin::Pinned,
+    rewind::RewindBuffer,
+    scenario::{spawn_body, BodySpec},
+    spacecraft::{hull_mesh, Spacecraft},
+    spin::Spin,
+    Celestial, DebugMarker, Name, Radius, SimClock, Universe,
+};
+
+const SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+struct SavedBody {
+    name: Option<String>,
+    celestial: Celestial,
+    state: SimState,
+    radius: f32,
+    color: Color,
+    rotation: Quat,
+    pinned: bool,
+    atmosphere: Option<Atmosphere>,
+    spin: Option<Spin>,
+    spacecraft: Option<Spacecraft>,
+    /// Index of the parent in the saved bodies.
+    parent: Option<usize>,
+    test_particle: bool,
+}
+
+struct Checkpoint {
+    universe: Universe,
+    clock: SimClock,
+    bodies: Vec<SavedBody>,
+}
+
+/// Snapshots of the whole simulation, kept in memory. The number keys pick a
+/// slot, F5 saves to it and F9 restores it. Restoring respawns every body,
+/// including tracers and spacecraft, with their spin and parent frame.
+#[derive(Default)]
+pub struct Checkpoints {
+    slot: usize,
+    slots: [Option<Checkpoint>; 9],
+}
+
+fn select_slot(keys: Res<Input<KeyCode>>, mut checkpoints: ResMut<Checkpoints>) {
+    if let Some(slot) = SLOT_KEYS.iter().position(|key| keys.just_pressed(*key)) {
+        checkpoints.slot = slot;
+        info!("checkpoint slot {}", slot + 1);
+    }
+}
+
+fn save_checkpoint(
+    keys: Res<Input<KeyCode>>,
+    universe: Res<Universe>,
+    clock: Res<SimClock>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut checkpoints: ResMut<Checkpoints>,
+    bodies: Query<
+        (
+            (Entity, &Celestial, &SimState, &Radius, &Transform),
+            Option<&Name>,
+            Option<&Handle<StandardMaterial>>,
+            Option<&Highlighting<StandardMaterial>>,
+            (Option<&Atmosphere>, Option<&Pinned>, Option<&Spin>),
+            (
+                Option<&Spacecraft>,
+                Option<&ParentBody>,
+                Option<&TestParticle>,
+            ),
+        ),
+        Without<DebugMarker>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
+    let entities: Vec<Entity> = bodies.iter().map(|((entity, ..), ..)| entity).collect();
+    let bodies: Vec<SavedBody> = bodies
+        .iter()
+        .map(
+            |(
+                (_, celestial, state, radius, transform),
+                name,
+                material,
+                highlighting,
+                (atmosphere, pinned, spin),
+                (spacecraft, parent, test_particle),
+            )| SavedBody {
+                name: name.map(|name| name.name.clone()),
+                celestial: celestial.clone(),
+                state: *state,
+                radius: radius.0,
+                color: material
+                    .and_then(|handle| materials.get(base_material(handle, highlighting)))
+                    .map_or(Color::WHITE, |material| material.base_color),
+                rotation: transform.rotation,
+                pinned: pinned.is_some(),
+                atmosphere: atmosphere.copied(),
+                spin: spin.copied(),
+                spacecraft: spacecraft.copied(),
+                parent: parent
+                    .and_then(|parent| entities.iter().position(|entity| *entity == parent.0)),
+                test_particle: test_particle.is_some(),
+            },
+        )
+        .collect();
+    let slot = checkpoints.slot;
+    info!("saved {} bodies to slot {}", bodies.len(), slot + 1);
+    checkpoints.slots[slot] = Some(Checkpoint {
+        universe: universe.clone(),
+        clock: clock.clone(),
+        bodies,
+    });
+}
+
+fn load_checkpoint(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    checkpoints: Res<Checkpoints>,
+    origin: Res<FloatingOrigin>,
+    mut universe: ResMut<Universe>,
+    mut clock: ResMut<SimClock>,
+    mut rewind: ResMut<RewindBuffer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<Entity, With<Celestial>>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let checkpoint = match &checkpoints.slots[checkpoints.slot] {
+        Some(checkpoint) => checkpoint,
+        None => {
+            info!("slot {} is empty", checkpoints.slot + 1);
+            return;
+        }
+    };
+    for entity in bodies.iter() {
+        commands.entity(entity).despawn();
+    }
+    *universe = checkpoint.universe.clone();
+    *clock = checkpoint.clock.clone();
+    rewind.clear();
+    let mut tracer = None;
+    let mut spawned = Vec::with_capacity(checkpoint.bodies.len());
+    for saved in checkpoint.bodies.iter() {
+        let position = origin.to_render(saved.state.position);
+        let entity = if saved.test_particle {
+            let (mesh, material) =
+                tracer.get_or_insert_with(|| tracer_assets(&mut meshes, &mut materials));
+            spawn_tracer(&mut commands, mesh, material, position, Vec3::ZERO)
+        } else {
+            spawn_body(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &universe,
+                BodySpec {
+                    name: saved.name.clone().unwrap_or_default(),
+                    mass: saved.celestial.mass,
+                    radius: saved.radius,
+                    color: saved.color,
+                    position,
+                    velocity: saved.celestial.velocity,
+                    parent: None,
+                },
+            )
+        };
+        spawned.push(entity);
+        // Inserted directly so positions keep their full precision.
+        let mut entity = commands.entity(entity);
+        entity
+            .insert(saved.celestial.clone())
+            .insert(saved.state)
+            .insert(
+                Transform::from_translation(position)
+                    .with_rotation(saved.rotation)
+                    .with_scale(Vec3::splat(saved.radius)),
+            );
+        if saved.name.is_none() {
+            entity.remove::<Name>();
+        }
+        if saved.pinned {
+            entity.insert(Pinned);
+        }
+        if let Some(atmosphere) = saved.atmosphere {
+            entity.insert(atmosphere);
+        }
+        match saved.spin {
+            Some(spin) => entity.insert(spin),
+            None => entity.remove::<Spin>(),
+        };
+        if let Some(spacecraft) = saved.spacecraft {
+            entity.insert(spacecraft).insert(meshes.add(hull_mesh()));
+        }
+    }
+    for (saved, entity) in checkpoint.bodies.iter().zip(spawned.iter()) {
+        if let Some(parent) = saved.parent {
+            commands.entity(*entity).insert(ParentBody(spawned[parent]));
+        }
+    }
+    info!(
+        "restored {} bodies from slot {}",
+        checkpoint.bodies.len(),
+        checkpoints.slot + 1
+    );
+}
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Checkpoints>()
+            .add_system(select_slot)
+            .add_system(save_checkpoint.after(select_slot))
+            .add_system(load_checkpoint.after(save_checkpoint));
+    }
+}
@@ -0,0 +1,287 @@
+use bevy::{ecs::schedule::RunCriteria, math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    origin::{FloatingOrigin, SimState},
+    particles::TestParticle,
+    pin::Pinned,
+    recording::not_replaying,
+    step_universe, Celestial, DebugMarker, Name, Radius, Universe, UniverseStep,
+};
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionResponse {
+    /// Bodies pass through each other.
+    #[default]
+    Ignore,
+    /// The lighter body is absorbed into the heavier one.
+    Merge,
+    /// Bodies bounce off each other along the collision normal.
+    Bounce,
+}
+
+impl CollisionResponse {
+    pub fn next(self) -> Self {
+        match self {
+            CollisionResponse::Ignore => CollisionResponse::Merge,
+            CollisionResponse::Merge => CollisionResponse::Bounce,
+            CollisionResponse::Bounce => CollisionResponse::Ignore,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionOutcome {
+    /// The other body was absorbed into `survivor`.
+    Merged {
+        survivor: Entity,
+    },
+    Bounced,
+}
+
+/// Sent for every pair of bodies that touched and were resolved this frame.
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    /// Point on the surface of `a` facing `b`, in render space.
+    pub contact: Vec3,
+    /// How fast the bodies were closing along the line between their centers.
+    pub impact_speed: f32,
+    pub outcome: CollisionOutcome,
+}
+
+/// A body in simulation space, so collisions far from the render origin are
+/// resolved at full precision.
+struct CollidingBody {
+    entity: Entity,
+    position: DVec3,
+    velocity: DVec3,
+    mass: f64,
+    radius: f64,
+    density: f32,
+    pinned: bool,
+    removed: bool,
+    dirty: bool,
+}
+
+fn merge(survivor: &mut CollidingBody, absorbed: &mut CollidingBody) {
+    let mass = survivor.mass + absorbed.mass;
+    // A pinned survivor stays where it is and the absorbed momentum is lost.
+    if !survivor.pinned {
+        survivor.position =
+            (survivor.position * survivor.mass + absorbed.position * absorbed.mass) / mass;
+        survivor.velocity =
+            (survivor.velocity * survivor.mass + absorbed.velocity * absorbed.mass) / mass;
+    }
+    survivor.mass = mass;
+
+    // Keep the combined volume of both spheres.
+    let radius = (survivor.radius.powi(3) + absorbed.radius.powi(3)).cbrt();
+    survivor.density = Celestial::density_for(mass as f32, radius as f32);
+    survivor.radius = radius;
+    survivor.dirty = true;
+    absorbed.removed = true;
+}
+
+/// Resolves a collision between two overlapping bodies along the line between
+/// their centers, conserving momentum. A `restitution` of 1 also conserves
+/// kinetic energy, 0 makes the bodies stick together along the normal.
+/// Pinned bodies behave as if they had infinite mass.
+fn bounce(a: &mut CollidingBody, b: &mut CollidingBody, restitution: f64) {
+    if a.pinned && b.pinned {
+        return;
+    }
+    let inverse_mass = |body: &CollidingBody| if body.pinned { 0.0 } else { 1.0 / body.mass };
+    let (inverse_a, inverse_b) = (inverse_mass(a), inverse_mass(b));
+
+    let normal = (b.position - a.position)
+        .try_normalize()
+        .unwrap_or(DVec3::X);
+    let closing_speed = (b.velocity - a.velocity).dot(normal);
+    if closing_speed < 0.0 {
+        let impulse = -(1.0 + restitution) * closing_speed / (inverse_a + inverse_b);
+        a.velocity -= normal * impulse * inverse_a;
+        b.velocity += normal * impulse * inverse_b;
+    }
+
+    // Push the bodies apart so they don't collide again next frame.
+    let overlap = a.radius + b.radius - a.position.distance(b.position);
+    let total = inverse_a + inverse_b;
+    a.position -= normal * overlap * (inverse_a / total);
+    b.position += normal * overlap * (inverse_b / total);
+    a.dirty = true;
+    b.dirty = true;
+}
+
+pub fn resolve_collisions(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut collisions: EventWriter<CollisionEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Celestial,
+            &mut SimState,
+            &mut Transform,
+            &mut Radius,
+            Option<&Pinned>,
+        ),
+        (Without<DebugMarker>, Without<TestParticle>),
+    >,
+) {
+    if universe.collision_response == CollisionResponse::Ignore {
+        return;
+    }
+
+    let mut bodies: Vec<CollidingBody> = query
+        .iter()
+        .map(|(entity, body, state, _, radius, pinned)| CollidingBody {
+            entity,
+            position: state.position,
+            velocity: state.velocity,
+            mass: body.mass as f64,
+            radius: radius.0 as f64,
+            density: body.density,
+            pinned: pinned.is_some(),
+            removed: false,
+            dirty: false,
+        })
+        .collect();
+
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let (head, tail) = bodies.split_at_mut(j);
+            let (a, b) = (&mut head[i], &mut tail[0]);
+            if a.removed || b.removed || a.position.distance(b.position) >= a.radius + b.radius {
+                continue;
+            }
+            let normal = (b.position - a.position)
+                .try_normalize()
+                .unwrap_or(DVec3::X);
+            let (a_entity, b_entity) = (a.entity, b.entity);
+            let contact = origin.to_render(a.position + normal * a.radius);
+            let impact_speed = (a.velocity - b.velocity).dot(normal).max(0.0) as f32;
+            let outcome = match universe.collision_response {
+                CollisionResponse::Ignore => continue,
+                CollisionResponse::Merge => {
+                    // Pinned bodies always survive so they stay put.
+                    let a_survives = (a.pinned, a.mass) >= (b.pinned, b.mass);
+                    let (survivor, absorbed) = if a_survives { (a, b) } else { (b, a) };
+                    merge(survivor, absorbed);
+                    CollisionOutcome::Merged {
+                        survivor: survivor.entity,
+                    }
+                }
+                CollisionResponse::Bounce => {
+                    bounce(a, b, universe.restitution as f64);
+                    CollisionOutcome::Bounced
+                }
+            };
+            collisions.send(CollisionEvent {
+                a: a_entity,
+                b: b_entity,
+                contact,
+                impact_speed,
+                outcome,
+            });
+        }
+    }
+
+    for body in bodies {
+        if body.removed {
+            commands.entity(body.entity).despawn();
+        } else if body.dirty {
+            let (_, mut celestial, mut state, mut transform, mut radius, _) =
+                query.get_mut(body.entity).unwrap();
+            state.position = body.position;
+            state.velocity = body.velocity;
+            celestial.velocity = body.velocity.as_vec3();
+            celestial.mass = body.mass as f32;
+            celestial.density = body.density;
+            radius.0 = body.radius as f32;
+            transform.translation = origin.to_render(body.position);
+            transform.scale = Vec3::splat(radius.0);
+        }
+    }
+}
+
+fn log_collisions(mut collisions: EventReader<CollisionEvent>, names: Query<&Name>) {
+    let name_of = |entity: Entity| {
+        names
+            .get(entity)
+            .map(|name| name.name.clone())
+            .unwrap_or_else(|_| format!("{:?}", entity))
+    };
+    for event in collisions.iter() {
+        let outcome = match event.outcome {
+            CollisionOutcome::Merged { survivor } => format!("merged into {}", name_of(survivor)),
+            CollisionOutcome::Bounced => "bounced".to_string(),
+        };
+        info!(
+            "{} and {} collided at ({:.1}, {:.1}, {:.1}) closing at {:.2}, {}",
+            name_of(event.a),
+            name_of(event.b),
+            event.contact.x,
+            event.contact.y,
+            event.contact.z,
+            event.impact_speed,
+            outcome
+        );
+    }
+}
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CollisionEvent>()
+            .add_system(
+                resolve_collisions
+                    .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                    .after(step_universe),
+            )
+            .add_system(log_collisions.after(resolve_collisions));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colliding(id: u32, x: f64, speed: f64, mass: f64) -> CollidingBody {
+        CollidingBody {
+            entity: Entity::from_raw(id),
+            position: DVec3::X * x,
+            velocity: DVec3::X * speed,
+            mass,
+            radius: 1.0,
+            density: 1.0,
+            pinned: false,
+            removed: false,
+            dirty: false,
+        }
+    }
+
+    #[test]
+    fn equal_masses_swap_velocities() {
+        let (mut a, mut b) = (colliding(0, -0.9, 2.0, 1.0), colliding(1, 0.9, -1.0, 1.0));
+        bounce(&mut a, &mut b, 1.0);
+        assert!(a.velocity.abs_diff_eq(DVec3::X * -1.0, 1e-12));
+        assert!(b.velocity.abs_diff_eq(DVec3::X * 2.0, 1e-12));
+        assert!(a.position.distance(b.position) >= 2.0 - 1e-12);
+    }
+
+    #[test]
+    fn unequal_masses_conserve_momentum_and_energy() {
+        let (mut a, mut b) = (colliding(0, -0.9, 1.0, 1.0), colliding(1, 0.9, -1.0, 3.0));
+        bounce(&mut a, &mut b, 1.0);
+        assert!(a.velocity.abs_diff_eq(DVec3::X * -2.0, 1e-12));
+        assert!(b.velocity.abs_diff_eq(DVec3::ZERO, 1e-12));
+        let momentum = a.velocity * a.mass + b.velocity * b.mass;
+        assert!(momentum.abs_diff_eq(DVec3::X * -2.0, 1e-12));
+        let energy =
+            0.5 * (a.mass * a.velocity.length_squared() + b.mass * b.velocity.length_squared());
+        assert!((energy - 2.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::FloatingOrigin,
+    overlay::{draw_wire_sphere, screen_position},
+    prediction::PredictedPath,
+    soi::SphereOfInfluence,
+    Name, Universe,
+};
+
+const ENTER_COLOR: Color = Color::rgb(0.4, 1.0, 0.6);
+const EXIT_COLOR: Color = Color::rgb(0.8, 0.5, 1.0);
+const MARKER_RADIUS: f32 = 0.4;
+
+/// A predicted path crossing into or out of another body's sphere of
+/// influence.
+pub struct SoiCrossing {
+    /// The body whose path crosses.
+    pub body: Entity,
+    /// The body whose sphere is crossed.
+    pub sphere: Entity,
+    /// Index into the crossing body's `PredictedPath::points`, the first step
+    /// on the new side.
+    pub index: usize,
+    pub entering: bool,
+}
+
+#[derive(Default)]
+pub struct SoiCrossings(pub Vec<SoiCrossing>);
+
+/// Steps at which `body` goes into or out of a sphere of `radius` around
+/// `center`, with whether it went in. Both are sampled at the same steps.
+pub fn sphere_crossings(body: &[DVec3], center: &[DVec3], radius: f64) -> Vec<(usize, bool)> {
+    let inside: Vec<bool> = body
+        .iter()
+        .zip(center)
+        .map(|(body, center)| body.distance(*center) < radius)
+        .collect();
+    inside
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0] != pair[1])
+        .map(|(i, pair)| (i + 1, pair[1]))
+        .collect()
+}
+
+/// Spheres are taken at their current size, carried along the predicted path
+/// of the body they belong to. They change size as that body's distance to
+/// its own parent changes, so crossings far out on an eccentric orbit are
+/// approximate.
+fn find_soi_crossings(
+    universe: Res<Universe>,
+    changed: Query<(), Changed<PredictedPath>>,
+    removed: RemovedComponents<PredictedPath>,
+    paths: Query<(Entity, &PredictedPath, &SphereOfInfluence)>,
+    mut crossings: ResMut<SoiCrossings>,
+    mut shown: Local<bool>,
+) {
+    let toggled = universe.show_soi_crossings != *shown;
+    *shown = universe.show_soi_crossings;
+    if changed.is_empty() && removed.iter().next().is_none() && !toggled {
+        return;
+    }
+    crossings.0.clear();
+    if !universe.show_soi_crossings {
+        return;
+    }
+    let paths: Vec<(Entity, &PredictedPath, &SphereOfInfluence)> = paths.iter().collect();
+    for (body, path, _) in paths.iter() {
+        for (sphere, sphere_path, influence) in paths.iter() {
+            if body == sphere || !influence.radius.is_finite() {
+                continue;
+            }
+            let found = sphere_crossings(&path.positions, &sphere_path.positions, influence.radius);
+            crossings
+                .0
+                .extend(found.into_iter().map(|(index, entering)| SoiCrossing {
+                    body: *body,
+                    sphere: *sphere,
+                    index,
+                    entering,
+                }));
+        }
+    }
+}
+
+/// A marker where each crossing happens, labeled with the sphere's body.
+fn draw_soi_crossings(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    crossings: Res<SoiCrossings>,
+    mut lines: ResMut<DebugLines>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    paths: Query<&PredictedPath>,
+    names: Query<&Name>,
+) {
+    if !universe.show_soi_crossings {
+        return;
+    }
+    let camera = camera.get_single().ok();
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for crossing in crossings.0.iter() {
+        let point = match paths
+            .get(crossing.body)
+            .ok()
+            .and_then(|path| path.points.get(crossing.index))
+        {
+            Some(point) => origin.to_render(*point),
+            None => continue,
+        };
+        let (color, verb) = if crossing.entering {
+            (ENTER_COLOR, "enter")
+        } else {
+            (EXIT_COLOR, "leave")
+        };
+        draw_wire_sphere(&mut lines, point, MARKER_RADIUS, color);
+        let screen =
+            camera.and_then(|(camera, transform)| screen_position(camera, transform, point));
+        if let Some(screen) = screen {
+            let name = names
+                .get(crossing.sphere)
+                .map(|name| name.name.clone())
+                .unwrap_or_else(|_| format!("{:?}", crossing.sphere));
+            let [r, g, b, _] = color.as_rgba_f32();
+            painter.text(
+                screen + egui::vec2(6.0, -6.0),
+                Align2::LEFT_BOTTOM,
+                format!("{} {}", verb, name),
+                FontId::proportional(12.0),
+                egui::Rgba::from_rgb(r, g, b).into(),
+            );
+        }
+    }
+}
+
+pub struct SoiCrossingPlugin;
+
+impl Plugin for SoiCrossingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoiCrossings>()
+            .add_system(find_soi_crossings)
+            .add_system(draw_soi_crossings.after(find_soi_crossings));
+    }
+}
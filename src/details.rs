@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui::EguiContext, egui};
+
+use crate::{
+    build_celestial_maps,
+    orbits::{dominant_attractor, escape_velocity},
+    origin::SimState,
+    pin::Pinned,
+    soi::SphereOfInfluence,
+    units::Units,
+    Celestial, DebugMarker, InspectTarget, Name, Universe,
+};
+
+fn name_of(names: &Query<&Name>, entity: Entity) -> String {
+    names
+        .get(entity)
+        .map(|name| name.name.clone())
+        .unwrap_or_else(|_| format!("{:?}", entity))
+}
+
+/// A speed in simulation units, followed by the same speed in m/s.
+fn speed_label(units: &Units, speed: f64) -> String {
+    format!("{:.3} ({:.3e} m/s)", speed, units.speed_to_si(speed))
+}
+
+fn details_panel_ui(
+    mut egui_context: ResMut<EguiContext>,
+    inspector: Res<InspectTarget>,
+    universe: Res<Universe>,
+    units: Res<Units>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    names: Query<&Name>,
+    pinned: Query<(), With<Pinned>>,
+    spheres: Query<&SphereOfInfluence>,
+) {
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    let celestial_map = build_celestial_maps(&bodies);
+    let body = match celestial_map.map.get(&target) {
+        Some(body) => body,
+        None => return,
+    };
+
+    egui::Window::new("Details").show(egui_context.ctx_mut(), |ui| {
+        egui::Grid::new("details_grid").show(ui, |ui| {
+            ui.label("Name");
+            ui.label(name_of(&names, target));
+            ui.end_row();
+            ui.label("Mass");
+            ui.label(format!(
+                "{:.3} ({:.3e} kg)",
+                body.mass,
+                units.mass_to_si(body.mass)
+            ));
+            ui.end_row();
+            ui.label("Speed");
+            ui.label(speed_label(&units, body.vel.length()));
+            ui.end_row();
+            ui.label("Pinned");
+            ui.label(if pinned.contains(target) { "yes" } else { "no" });
+            ui.end_row();
+
+            let sphere = spheres.get(target).ok();
+            ui.label("Sphere of influence");
+            ui.label(match sphere {
+                Some(sphere) if sphere.radius.is_finite() => format!(
+                    "{:.3} ({:.3e} m)",
+                    sphere.radius,
+                    units.length_to_si(sphere.radius)
+                ),
+                Some(_) => "unbounded".to_string(),
+                None => "n/a".to_string(),
+            });
+            ui.end_row();
+            ui.label("Inside sphere of");
+            match sphere.and_then(|sphere| sphere.dominant) {
+                Some(entity) => ui.label(name_of(&names, entity)),
+                None => ui.label("n/a"),
+            };
+            ui.end_row();
+
+            let attractor = dominant_attractor(&celestial_map, target)
+                .and_then(|entity| celestial_map.map.get(&entity).map(|a| (entity, a)));
+            ui.label("Attractor");
+            match attractor {
+                Some((entity, _)) => ui.label(name_of(&names, entity)),
+                None => ui.label("n/a"),
+            };
+            ui.end_row();
+
+            let escape = attractor.map(|(_, attractor)| {
+                let relative_speed = (body.vel - attractor.vel).length();
+                let distance = body.pos.distance(attractor.pos);
+                let escape = escape_velocity(
+                    universe.gravitational_constant as f64,
+                    attractor.mass,
+                    distance,
+                );
+                (relative_speed, escape)
+            });
+            ui.label("Relative speed");
+            ui.label(escape.map_or("n/a".to_string(), |(speed, _)| speed_label(&units, speed)));
+            ui.end_row();
+            ui.label("Escape velocity");
+            ui.label(escape.map_or("n/a".to_string(), |(_, escape)| speed_label(&units, escape)));
+            ui.end_row();
+            ui.label("Orbit");
+            ui.label(match escape {
+                Some((speed, escape)) if speed < escape => "bound",
+                Some(_) => "escaping",
+                None => "n/a",
+            });
+            ui.end_row();
+        });
+    });
+}
+
+pub struct DetailsPlugin;
+
+impl Plugin for DetailsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(details_panel_ui);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+
+use bevy::{math::DVec3, prelude::*};
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{
+        self,
+        plot::{Legend, Line, Plot, Value, Values},
+    },
+    Inspectable,
+};
+
+use crate::{
+    build_celestial_maps,
+    origin::SimState,
+    physics::{system_energy, system_momentum},
+    step_universe,
+    units::{format_duration, Units},
+    Celestial, DebugMarker, SimClock, StepBudget, Universe,
+};
+
+const HISTORY_LENGTH: usize = 2000;
+/// How long the falling-behind warning stays up after the step cap was last hit.
+const CAPPED_WARNING_SECONDS: f64 = 2.0;
+
+/// Quantities a closed system should conserve, recomputed after every tick.
+/// How far they wander shows how well the integrator is doing.
+#[derive(Inspectable, Default)]
+pub struct ConservationStats {
+    pub kinetic_energy: f64,
+    pub potential_energy: f64,
+    pub total_energy: f64,
+    pub linear_momentum: DVec3,
+    /// About the simulation origin.
+    pub angular_momentum: DVec3,
+}
+
+pub struct EnergyHistory {
+    kinetic: VecDeque<Value>,
+    potential: VecDeque<Value>,
+    total: VecDeque<Value>,
+    last_step: u64,
+    autoscale: bool,
+}
+
+impl Default for EnergyHistory {
+    fn default() -> Self {
+        Self {
+            kinetic: VecDeque::with_capacity(HISTORY_LENGTH),
+            potential: VecDeque::with_capacity(HISTORY_LENGTH),
+            total: VecDeque::with_capacity(HISTORY_LENGTH),
+            last_step: 0,
+            autoscale: true,
+        }
+    }
+}
+
+impl EnergyHistory {
+    fn clear(&mut self) {
+        self.kinetic.clear();
+        self.potential.clear();
+        self.total.clear();
+    }
+
+    fn push(&mut self, time: f64, stats: &ConservationStats) {
+        for (series, value) in [
+            (&mut self.kinetic, stats.kinetic_energy),
+            (&mut self.potential, stats.potential_energy),
+            (&mut self.total, stats.total_energy),
+        ] {
+            if series.len() == HISTORY_LENGTH {
+                series.pop_front();
+            }
+            series.push_back(Value::new(time, value));
+        }
+    }
+}
+
+fn update_conservation_stats(
+    constants: Res<Universe>,
+    clock: Res<SimClock>,
+    query: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    mut stats: ResMut<ConservationStats>,
+    mut history: ResMut<EnergyHistory>,
+    mut sampled_step: Local<Option<u64>>,
+) {
+    // The inspector borrows the clock mutably every frame, so change
+    // detection can't tell whether the simulation actually stepped.
+    if *sampled_step == Some(clock.step_count) {
+        return;
+    }
+    *sampled_step = Some(clock.step_count);
+    if clock.step_count < history.last_step {
+        history.clear();
+    }
+    history.last_step = clock.step_count;
+
+    let celestial_map = build_celestial_maps(&query);
+    let (kinetic, potential) = system_energy(&constants, &celestial_map);
+    let (linear, angular) = system_momentum(&constants, &celestial_map);
+    stats.kinetic_energy = kinetic;
+    stats.potential_energy = potential;
+    stats.total_energy = kinetic + potential;
+    stats.linear_momentum = linear;
+    stats.angular_momentum = angular;
+    if clock.step_count > 0 {
+        history.push(clock.elapsed_seconds, &stats);
+    }
+}
+
+fn energy_plot_ui(mut egui_context: ResMut<EguiContext>, mut history: ResMut<EnergyHistory>) {
+    egui::Window::new("Energy").show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("Clear").clicked() {
+                history.clear();
+            }
+            ui.checkbox(&mut history.autoscale, "Autoscale Y");
+        });
+
+        if history.total.len() < 2 {
+            ui.label("Waiting for simulation ticks...");
+            return;
+        }
+
+        // A fresh plot id resets any manual zoom when autoscale is switched back on.
+        let plot = Plot::new(("energy_plot", history.autoscale))
+            .height(200.0)
+            .legend(Legend::default());
+        let plot = if history.autoscale {
+            plot.allow_drag(false).allow_zoom(false).allow_scroll(false)
+        } else {
+            plot
+        };
+        plot.show(ui, |plot_ui| {
+            for (name, series) in [
+                ("kinetic", &history.kinetic),
+                ("potential", &history.potential),
+                ("total", &history.total),
+            ] {
+                let values = Values::from_values_iter(series.iter().copied());
+                plot_ui.line(Line::new(values).name(name));
+            }
+        });
+    });
+}
+
+/// Warns while frames are too slow for the simulation to keep up and steps
+/// are being dropped.
+fn step_budget_warning(
+    time: Res<Time>,
+    universe: Res<Universe>,
+    budget: Res<StepBudget>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    match budget.last_capped {
+        Some(at) if time.seconds_since_startup() - at < CAPPED_WARNING_SECONDS => {}
+        _ => return,
+    }
+    egui::Window::new("Falling behind").show(egui_context.ctx_mut(), |ui| {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!(
+                "Frames are hitting the limit of {} steps",
+                universe.max_steps_per_frame
+            ),
+        );
+        ui.label(format!(
+            "{:.3} simulation seconds skipped so far",
+            budget.lost_seconds
+        ));
+    });
+}
+
+/// Simulated time in the corner of the screen, with its real-world length
+/// when the scenario is in physical units.
+fn clock_hud(
+    universe: Res<Universe>,
+    units: Res<Units>,
+    clock: Res<SimClock>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Area::new("clock_hud")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let mut elapsed = format!("t = {:.2}", clock.elapsed_seconds);
+            if *units != Units::default() {
+                elapsed += &format!(
+                    " ({})",
+                    format_duration(units.time_to_si(clock.elapsed_seconds))
+                );
+            }
+            ui.label(elapsed);
+            ui.label(format!(
+                "step {}, {}x{}",
+                clock.step_count,
+                universe.time_scale,
+                if universe.active { "" } else { ", paused" }
+            ));
+        });
+}
+
+pub struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConservationStats>()
+            .init_resource::<EnergyHistory>()
+            .add_system(update_conservation_stats.after(step_universe))
+            .add_system(energy_plot_ui)
+            .add_system(step_budget_warning)
+            .add_system(clock_hud);
+    }
+}
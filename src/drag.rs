@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+use bevy_flycam::FlyCam;
+use bevy_mod_picking::{PickingCamera, Primitive3d};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{overlay::draw_arrow, state::outside_menu, Celestial, DebugMarker, Universe};
+
+/// Marks a body that is being moved by the mouse. It still pulls on other
+/// bodies but the simulation leaves its own position and velocity alone.
+#[derive(Component)]
+pub struct Held;
+
+/// How far, in pixels, the cursor has to move with the button down before a
+/// press on a body becomes a drag rather than a click.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+struct ActiveDrag {
+    entity: Entity,
+    plane_point: Vec3,
+    plane_normal: Vec3,
+    offset: Vec3,
+    /// Where the cursor was pressed, until it moves far enough to drag.
+    pressed_at: Option<Vec2>,
+    keep_momentum: bool,
+}
+
+struct VelocityDrag {
+    entity: Entity,
+    plane_normal: Vec3,
+    end: Vec3,
+}
+
+#[derive(Default)]
+pub struct DragState {
+    active: Option<ActiveDrag>,
+    velocity: Option<VelocityDrag>,
+}
+
+fn drag_plane_normal(universe: &Universe, camera: &Transform) -> Vec3 {
+    if universe.planar {
+        Vec3::Y
+    } else {
+        camera.forward()
+    }
+}
+
+/// A press on a body only starts tracking it. Nothing happens to the body
+/// until the cursor moves past `DRAG_THRESHOLD`, so clicking to select or
+/// focus leaves it alone.
+fn start_drag(
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    universe: Res<Universe>,
+    mut state: ResMut<DragState>,
+    picking_camera: Query<(&PickingCamera, &Transform), With<FlyCam>>,
+    bodies: Query<&Transform, (With<Celestial>, Without<DebugMarker>, Without<FlyCam>)>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || state.active.is_some() {
+        return;
+    }
+    let (source, camera_transform) = match picking_camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let entity = match source.intersect_top() {
+        Some((entity, _)) => entity,
+        None => return,
+    };
+    let transform = match bodies.get(entity) {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    let pressed_at = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    let plane_point = universe.constrain(transform.translation);
+    let plane_normal = drag_plane_normal(&universe, camera_transform);
+    let hit = match source.intersect_primitive(Primitive3d::Plane {
+        point: plane_point,
+        normal: plane_normal,
+    }) {
+        Some(hit) => hit.position(),
+        None => return,
+    };
+
+    state.active = Some(ActiveDrag {
+        entity,
+        plane_point,
+        plane_normal,
+        offset: plane_point - hit,
+        pressed_at: Some(pressed_at),
+        keep_momentum: keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]),
+    });
+}
+
+fn update_drag(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut state: ResMut<DragState>,
+    picking_camera: Query<&PickingCamera>,
+    mut bodies: Query<(&mut Celestial, &mut Transform), Without<FlyCam>>,
+) {
+    let drag = match state.active.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+    if !mouse.pressed(MouseButton::Left) {
+        if drag.pressed_at.is_none() && bodies.contains(drag.entity) {
+            commands.entity(drag.entity).remove::<Held>();
+        }
+        state.active = None;
+        return;
+    }
+    let (mut body, mut transform) = match bodies.get_mut(drag.entity) {
+        Ok(body) => body,
+        Err(_) => {
+            state.active = None;
+            return;
+        }
+    };
+    if let Some(pressed_at) = drag.pressed_at {
+        let cursor = windows
+            .get_primary()
+            .and_then(|window| window.cursor_position());
+        if !cursor.is_some_and(|cursor| cursor.distance(pressed_at) > DRAG_THRESHOLD) {
+            return;
+        }
+        drag.pressed_at = None;
+        // Alt keeps the body's momentum, otherwise it is dropped at rest.
+        if !drag.keep_momentum {
+            body.velocity = Vec3::ZERO;
+        }
+        commands.entity(drag.entity).insert(Held);
+    }
+
+    let hit = picking_camera.get_single().ok().and_then(|source| {
+        source.intersect_primitive(Primitive3d::Plane {
+            point: drag.plane_point,
+            normal: drag.plane_normal,
+        })
+    });
+    if let Some(hit) = hit {
+        transform.translation = hit.position() + drag.offset;
+    }
+}
+
+/// While paused, right-dragging away from a body sets its velocity to the
+/// scaled drag vector. An arrow previews the result until the button is released.
+fn start_velocity_drag(
+    mouse: Res<Input<MouseButton>>,
+    universe: Res<Universe>,
+    mut state: ResMut<DragState>,
+    picking_camera: Query<(&PickingCamera, &Transform), With<FlyCam>>,
+    bodies: Query<&Transform, (With<Celestial>, Without<DebugMarker>, Without<FlyCam>)>,
+) {
+    if universe.active || !mouse.just_pressed(MouseButton::Right) || state.velocity.is_some() {
+        return;
+    }
+    let (source, camera_transform) = match picking_camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let (entity, transform) = match source
+        .intersect_top()
+        .and_then(|(entity, _)| bodies.get(entity).ok().map(|t| (entity, t)))
+    {
+        Some(hit) => hit,
+        None => return,
+    };
+    state.velocity = Some(VelocityDrag {
+        entity,
+        plane_normal: drag_plane_normal(&universe, camera_transform),
+        end: transform.translation,
+    });
+}
+
+fn update_velocity_drag(
+    mouse: Res<Input<MouseButton>>,
+    universe: Res<Universe>,
+    mut state: ResMut<DragState>,
+    mut lines: ResMut<DebugLines>,
+    picking_camera: Query<&PickingCamera>,
+    mut bodies: Query<(&mut Celestial, &Transform), (Without<DebugMarker>, Without<FlyCam>)>,
+) {
+    let drag = match state.velocity.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+    let (mut body, transform) = match bodies.get_mut(drag.entity) {
+        Ok(body) => body,
+        Err(_) => {
+            state.velocity = None;
+            return;
+        }
+    };
+    let start = transform.translation;
+
+    let hit = picking_camera.get_single().ok().and_then(|source| {
+        source.intersect_primitive(Primitive3d::Plane {
+            point: start,
+            normal: drag.plane_normal,
+        })
+    });
+    if let Some(hit) = hit {
+        drag.end = hit.position();
+    }
+    let velocity = universe.constrain((drag.end - start) * universe.velocity_drag_scale);
+
+    if mouse.pressed(MouseButton::Right) {
+        draw_arrow(
+            &mut lines,
+            start,
+            start + velocity / universe.velocity_drag_scale,
+            Color::YELLOW,
+        );
+    } else {
+        body.velocity = velocity;
+        state.velocity = None;
+    }
+}
+
+pub struct DragPlugin;
+
+impl Plugin for DragPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DragState>()
+            .add_system(start_drag.with_run_criteria(outside_menu))
+            .add_system(update_drag.after(start_drag))
+            .add_system(start_velocity_drag.with_run_criteria(outside_menu))
+            .add_system(update_velocity_drag.after(start_velocity_drag));
+    }
+}
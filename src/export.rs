@@ -0,0 +1,215 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+
+use crate::{rewind::RewindBuffer, Name};
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// An array of objects with the same fields as the CSV columns.
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportSource {
+    /// The trajectory predicted from now, sampled like the drawn prediction.
+    #[default]
+    Prediction,
+    /// The steps already run, as far back as the rewind buffer goes.
+    History,
+}
+
+#[derive(Inspectable)]
+pub struct TrajectoryExport {
+    /// Written with the extension of `format`, whatever this one ends in.
+    pub path: String,
+    pub format: ExportFormat,
+    pub source: ExportSource,
+}
+
+impl Default for TrajectoryExport {
+    fn default() -> Self {
+        Self {
+            path: "trajectory.csv".to_string(),
+            format: ExportFormat::default(),
+            source: ExportSource::default(),
+        }
+    }
+}
+
+impl TrajectoryExport {
+    pub fn output_path(&self) -> PathBuf {
+        Path::new(&self.path).with_extension(self.format.extension())
+    }
+}
+
+#[derive(Default)]
+pub struct ExportTrajectoryEvent;
+
+/// One body at one step, in simulation units.
+pub struct TrajectorySample {
+    pub name: String,
+    /// Steps since the scenario was loaded, like `SimClock::step_count`.
+    pub step: u64,
+    pub time: f64,
+    pub position: DVec3,
+    pub velocity: DVec3,
+}
+
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn write_trajectory_csv(path: &Path, samples: &[TrajectorySample]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "entity_name,step,time,x,y,z,vx,vy,vz")?;
+    for sample in samples {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            escape_csv_field(&sample.name),
+            sample.step,
+            sample.time,
+            sample.position.x,
+            sample.position.y,
+            sample.position.z,
+            sample.velocity.x,
+            sample.velocity.y,
+            sample.velocity.z
+        )?;
+    }
+    writer.flush()
+}
+
+pub fn escape_json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// JSON has no infinities or NaN, so those are written as null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+pub fn write_trajectory_json(path: &Path, samples: &[TrajectorySample]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "[")?;
+    for (i, sample) in samples.iter().enumerate() {
+        let separator = if i + 1 < samples.len() { "," } else { "" };
+        writeln!(
+            writer,
+            "  {{\"entity_name\": {}, \"step\": {}, \"time\": {}, \"x\": {}, \"y\": {}, \"z\": {}, \"vx\": {}, \"vy\": {}, \"vz\": {}}}{}",
+            escape_json_string(&sample.name),
+            sample.step,
+            json_number(sample.time),
+            json_number(sample.position.x),
+            json_number(sample.position.y),
+            json_number(sample.position.z),
+            json_number(sample.velocity.x),
+            json_number(sample.velocity.y),
+            json_number(sample.velocity.z),
+            separator
+        )?;
+    }
+    writeln!(writer, "]")?;
+    writer.flush()
+}
+
+pub fn export_trajectory(settings: &TrajectoryExport, samples: &[TrajectorySample]) {
+    let path = settings.output_path();
+    let written = match settings.format {
+        ExportFormat::Csv => write_trajectory_csv(&path, samples),
+        ExportFormat::Json => write_trajectory_json(&path, samples),
+    };
+    match written {
+        Ok(()) => info!(
+            "exported {} trajectory samples to {}",
+            samples.len(),
+            path.display()
+        ),
+        Err(err) => error!("failed to export trajectory to {}: {}", path.display(), err),
+    }
+}
+
+/// X exports the same as the inspector's button.
+fn export_key(keys: Res<Input<KeyCode>>, mut events: EventWriter<ExportTrajectoryEvent>) {
+    if keys.just_pressed(KeyCode::X) {
+        events.send(ExportTrajectoryEvent);
+    }
+}
+
+/// The prediction is exported by the system that makes it. History comes
+/// from the rewind buffer, so bodies destroyed since a step are still in it
+/// under their entity id.
+fn export_history(
+    mut events: EventReader<ExportTrajectoryEvent>,
+    settings: Res<TrajectoryExport>,
+    buffer: Res<RewindBuffer>,
+    names: Query<&Name>,
+) {
+    if events.iter().count() == 0 || settings.source != ExportSource::History {
+        return;
+    }
+    let samples: Vec<TrajectorySample> = buffer
+        .history()
+        .map(|(clock, entity, position, velocity)| TrajectorySample {
+            name: names
+                .get(entity)
+                .map(|name| name.name.clone())
+                .unwrap_or_else(|_| format!("{:?}", entity)),
+            step: clock.step_count,
+            time: clock.elapsed_seconds,
+            position,
+            velocity,
+        })
+        .collect();
+    export_trajectory(&settings, &samples);
+}
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrajectoryExport>()
+            .add_event::<ExportTrajectoryEvent>()
+            .add_system(export_key)
+            .add_system(export_history.after(export_key));
+    }
+}
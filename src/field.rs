@@ -0,0 +1,81 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    build_celestial_maps,
+    origin::{FloatingOrigin, SimState},
+    overlay::draw_arrow,
+    physics::field_at,
+    vectors::arrow_length,
+    Celestial, DebugMarker, Universe,
+};
+
+const FIELD_COLOR: Color = Color::rgb(0.3, 0.6, 0.9);
+
+/// F3 shows or hides the field arrows.
+fn toggle_field_arrows(keys: Res<Input<KeyCode>>, mut universe: ResMut<Universe>) {
+    if keys.just_pressed(KeyCode::F3) {
+        universe.show_field = !universe.show_field;
+    }
+}
+
+/// Samples the field on a grid around the camera, on the XZ plane or, with
+/// `field_3d`, through the space around it. The grid is snapped to its own
+/// spacing so the arrows stay put as the camera moves. Arrows are scaled like
+/// acceleration arrows, but never longer than the spacing, so they don't run
+/// into each other close to a body.
+fn draw_field_arrows(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut lines: ResMut<DebugLines>,
+    camera: Query<&GlobalTransform, With<FlyCam>>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+) {
+    if !universe.show_field {
+        return;
+    }
+    let camera = match camera.get_single() {
+        Ok(transform) => origin.to_simulation(transform.translation()),
+        Err(_) => return,
+    };
+    let celestial_map = build_celestial_maps(&bodies);
+    let spacing = universe.field_spacing.max(0.1) as f64;
+    let snap = |value: f64| (value / spacing).round() * spacing;
+    let center = if universe.field_3d {
+        DVec3::new(snap(camera.x), snap(camera.y), snap(camera.z))
+    } else {
+        DVec3::new(snap(camera.x), 0.0, snap(camera.z))
+    };
+    let half = universe.field_resolution as i32 / 2;
+    let heights = if universe.field_3d {
+        -half..=half
+    } else {
+        0..=0
+    };
+    for y in heights {
+        for x in -half..=half {
+            for z in -half..=half {
+                let point = center + DVec3::new(x as f64, y as f64, z as f64) * spacing;
+                let field = field_at(&universe, &celestial_map, point).as_vec3();
+                let length = arrow_length(
+                    field.length() * universe.acceleration_scale,
+                    universe.vector_scale,
+                )
+                .min(spacing as f32 * 0.8);
+                let start = origin.to_render(point);
+                let end = start + field.normalize_or_zero() * length;
+                draw_arrow(&mut lines, start, end, FIELD_COLOR);
+            }
+        }
+    }
+}
+
+pub struct FieldPlugin;
+
+impl Plugin for FieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_field_arrows)
+            .add_system(draw_field_arrows.after(toggle_field_arrows));
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::{math::DVec3, prelude::*};
+
+use crate::{physics::CelestialMap, DebugManager, InspectTarget, Name};
+
+/// The body this one was set up relative to, like the Earth for the Moon. The
+/// prediction can be drawn in that body's frame, so a moon's path shows its
+/// orbit rather than a wiggle along the planet's.
+#[derive(Component, Clone, Copy)]
+pub struct ParentBody(pub Entity);
+
+/// `position` of a body in `predicted`, moved into the frame of `parent` as
+/// it is in `current`. Falls back to `position` if the parent is gone.
+pub fn in_parent_frame(
+    current: &CelestialMap,
+    predicted: &CelestialMap,
+    parent: Entity,
+    position: DVec3,
+) -> DVec3 {
+    match (current.map.get(&parent), predicted.map.get(&parent)) {
+        (Some(now), Some(then)) => position - then.pos + now.pos,
+        _ => position,
+    }
+}
+
+/// A body whose motion is taken out of every predicted path, so anything
+/// orbiting it is drawn as a closed curve even while it moves. Overrides the
+/// parent frames while set. E makes the selected body the reference, or
+/// clears it if it already is.
+#[derive(Default)]
+pub struct ReferenceFrame {
+    pub body: Option<Entity>,
+}
+
+fn set_reference_frame(
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    names: Query<&Name>,
+    mut frame: ResMut<ReferenceFrame>,
+    mut manager: ResMut<DebugManager>,
+) {
+    if !keys.just_pressed(KeyCode::E) {
+        return;
+    }
+    frame.body = match inspector.target {
+        Some(target) if frame.body != Some(target) => Some(target),
+        _ => None,
+    };
+    match frame.body.and_then(|body| names.get(body).ok()) {
+        Some(name) => info!("predicting relative to {}", name.name),
+        None if frame.body.is_some() => info!("predicting relative to {:?}", frame.body),
+        None => info!("predicting in the simulation frame"),
+    }
+    manager.refresh = true;
+}
+
+pub struct FramePlugin;
+
+impl Plugin for FramePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReferenceFrame>()
+            .add_system(set_reference_frame);
+    }
+}
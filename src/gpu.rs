@@ -0,0 +1,210 @@
+use std::sync::OnceLock;
+
+use bevy::{
+    core::cast_slice,
+    math::DVec3,
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BufferDescriptor, BufferUsages,
+            ComputePassDescriptor, MapMode, ShaderModuleDescriptor, ShaderSource,
+        },
+        renderer::{RenderDevice, RenderQueue},
+    },
+};
+use wgpu::util::BufferInitDescriptor;
+
+use crate::physics::{CelestialBundle, Gravity};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+const SHADER: &str = r#"
+struct Params {
+    count: u32,
+    gravitational_constant: f32,
+    softening_squared: f32,
+    padding: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+// xyz is the position, w the mass.
+@group(0) @binding(1) var<storage, read> bodies: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> accelerations: array<vec4<f32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let position = bodies[index].xyz;
+    var acceleration = vec3<f32>(0.0, 0.0, 0.0);
+    for (var other = 0u; other < params.count; other = other + 1u) {
+        if (other == index) {
+            continue;
+        }
+        let body = bodies[other];
+        let offset = body.xyz - position;
+        let square_distance = dot(offset, offset) + params.softening_squared;
+        if (square_distance > 0.0) {
+            acceleration = acceleration
+                + offset * (params.gravitational_constant * body.w
+                    / (square_distance * sqrt(square_distance)));
+        }
+    }
+    accelerations[index] = vec4<f32>(acceleration, 0.0);
+}
+"#;
+
+/// Direct-summation gravity on the GPU. Only set up when the render device
+/// supports compute shaders; everything else falls back to the CPU path.
+struct GpuForces {
+    device: RenderDevice,
+    queue: RenderQueue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+static GPU_FORCES: OnceLock<GpuForces> = OnceLock::new();
+
+fn supports_compute(device: &RenderDevice) -> bool {
+    let limits = device.limits();
+    limits.max_compute_workgroup_size_x >= WORKGROUP_SIZE
+        && limits.max_compute_invocations_per_workgroup >= WORKGROUP_SIZE
+        && limits.max_storage_buffers_per_shader_stage >= 2
+}
+
+fn init_gpu_forces(device: Option<Res<RenderDevice>>, queue: Option<Res<RenderQueue>>) {
+    let (device, queue) = match (device, queue) {
+        (Some(device), Some(queue)) => (device, queue),
+        _ => return,
+    };
+    if !supports_compute(&device) {
+        warn!("compute shaders unavailable, gravity stays on the CPU");
+        return;
+    }
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("nbody_gravity_shader"),
+        source: ShaderSource::Wgsl(SHADER.into()),
+    });
+    let pipeline = device
+        .wgpu_device()
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("nbody_gravity_pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+    let _ = GPU_FORCES.set(GpuForces {
+        device: device.clone(),
+        queue: queue.clone(),
+        pipeline,
+    });
+}
+
+/// Gravitational acceleration of every body, or `None` when no compute
+/// capable device is available. Blocks until the GPU has finished.
+pub(crate) fn gravity_accelerations(
+    gravity: &Gravity,
+    bodies: &[CelestialBundle],
+) -> Option<Vec<DVec3>> {
+    let gpu = GPU_FORCES.get()?;
+    if bodies.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let count = bodies.len() as u32;
+    let params = [
+        count,
+        (gravity.gravitational_constant as f32).to_bits(),
+        ((gravity.softening * gravity.softening) as f32).to_bits(),
+        0,
+    ];
+    // The shader works in f32, so positions go up relative to one of the
+    // bodies rather than to a possibly distant simulation origin.
+    let anchor = bodies[0].pos;
+    let packed: Vec<[f32; 4]> = bodies
+        .iter()
+        .map(|body| {
+            (body.pos - anchor)
+                .as_vec3()
+                .extend(body.mass as f32)
+                .to_array()
+        })
+        .collect();
+    let output_size = (bodies.len() * std::mem::size_of::<[f32; 4]>()) as u64;
+
+    let params = gpu.device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("nbody_params"),
+        contents: cast_slice(&params),
+        usage: BufferUsages::UNIFORM,
+    });
+    let input = gpu.device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("nbody_bodies"),
+        contents: cast_slice(&packed),
+        usage: BufferUsages::STORAGE,
+    });
+    let output = gpu.device.create_buffer(&BufferDescriptor {
+        label: Some("nbody_accelerations"),
+        size: output_size,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let readback = gpu.device.create_buffer(&BufferDescriptor {
+        label: Some("nbody_readback"),
+        size: output_size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+        label: Some("nbody_bind_group"),
+        layout: &gpu.pipeline.get_bind_group_layout(0),
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: input.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: output.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("nbody_encoder"),
+        });
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("nbody_pass"),
+        });
+        pass.set_pipeline(&gpu.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, output_size);
+    gpu.queue.submit([encoder.finish()]);
+
+    let slice = readback.slice(..);
+    gpu.device.map_buffer(&slice, MapMode::Read, |_| {});
+    gpu.device.poll(wgpu::Maintain::Wait);
+    let accelerations = cast_slice::<u8, [f32; 4]>(&slice.get_mapped_range())
+        .iter()
+        .map(|[x, y, z, _]| Vec3::new(*x, *y, *z).as_dvec3())
+        .collect();
+    readback.unmap();
+    Some(accelerations)
+}
+
+pub struct GpuForcesPlugin;
+
+impl Plugin for GpuForcesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(init_gpu_forces);
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use bevy::{asset::AssetPlugin, input::InputPlugin, prelude::*};
+
+use crate::{
+    benchmark::run_benchmark,
+    export::escape_csv_field,
+    origin::SimState,
+    physics::{system_energy, system_momentum, CelestialBundle, CelestialMap, IntegratorKind},
+    scenario::Scenario,
+    setup_universe, Celestial, Name, SimClock, SimulationCommand, Universe, UniversePlugin,
+};
+
+const USAGE: &str = "usage: --headless [--scenario NAME] [--steps N | --duration SECONDS] \
+                     [--output PATH]\n       --benchmark BODIES [--steps N] [--barnes-hut] \
+                     [--integrator euler|leapfrog|rk4] [--output PATH]";
+
+/// What a headless run simulates and where it reports to. With `benchmark`
+/// set it times that many random bodies instead of running a scenario.
+pub struct HeadlessOptions {
+    scenario: Scenario,
+    benchmark: Option<usize>,
+    barnes_hut: bool,
+    integrator: IntegratorKind,
+    steps: Option<u64>,
+    duration: Option<f64>,
+    output: Option<String>,
+}
+
+impl HeadlessOptions {
+    /// Parses the command line, minus the program name. Returns `None` unless
+    /// `--headless` or `--benchmark` is among the arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut headless = false;
+        let mut options = HeadlessOptions {
+            scenario: Scenario::default(),
+            benchmark: None,
+            barnes_hut: false,
+            integrator: IntegratorKind::default(),
+            steps: None,
+            duration: None,
+            output: None,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or(format!("{} needs a value\n{}", arg, USAGE))
+            };
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--scenario" => {
+                    let name = value()?;
+                    options.scenario = Scenario::ALL
+                        .into_iter()
+                        .find(|scenario| format!("{:?}", scenario).eq_ignore_ascii_case(&name))
+                        .ok_or(format!("unknown scenario {}", name))?;
+                }
+                "--benchmark" => {
+                    headless = true;
+                    options.benchmark = Some(
+                        value()?
+                            .parse()
+                            .map_err(|err| format!("--benchmark: {}", err))?,
+                    )
+                }
+                "--barnes-hut" => options.barnes_hut = true,
+                "--integrator" => {
+                    let name = value()?;
+                    options.integrator = IntegratorKind::ALL
+                        .into_iter()
+                        .find(|kind| format!("{:?}", kind).eq_ignore_ascii_case(&name))
+                        .ok_or(format!("unknown integrator {}", name))?;
+                }
+                "--steps" => {
+                    options.steps = Some(
+                        value()?
+                            .parse()
+                            .map_err(|err| format!("--steps: {}", err))?,
+                    )
+                }
+                "--duration" => {
+                    options.duration = Some(
+                        value()?
+                            .parse()
+                            .map_err(|err| format!("--duration: {}", err))?,
+                    )
+                }
+                "--output" => options.output = Some(value()?),
+                _ => return Err(format!("unknown argument {}\n{}", arg, USAGE)),
+            }
+        }
+        Ok(headless.then_some(options))
+    }
+}
+
+/// Builds the simulation without rendering, picking or the inspector. It only
+/// moves when told to with `SimulationCommand::Step`, so runs don't depend on
+/// how fast the machine is.
+fn headless_app(scenario: Scenario) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(UniversePlugin)
+        .add_asset::<Mesh>()
+        .add_asset::<StandardMaterial>()
+        .add_startup_system(setup_universe);
+    let mut universe = app.world.resource_mut::<Universe>();
+    universe.scenario = scenario;
+    universe.active = false;
+    // Let the bodies spawn and pick up their simulation state.
+    app.update();
+    app.update();
+    app
+}
+
+fn celestial_map(world: &mut World) -> CelestialMap {
+    let mut bodies = world.query::<(Entity, &Celestial, &SimState)>();
+    CelestialMap {
+        map: bodies
+            .iter(world)
+            .map(|(entity, body, state)| {
+                (
+                    entity,
+                    CelestialBundle {
+                        pos: state.position,
+                        vel: state.velocity,
+                        acc: state.acceleration,
+                        mass: body.mass as f64,
+                        charge: body.charge as f64,
+                        pinned: false,
+                        radius: body.radius() as f64,
+                        atmosphere: None,
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+fn total_energy(world: &mut World) -> f64 {
+    let celestial_map = celestial_map(world);
+    let (kinetic, potential) = system_energy(world.resource::<Universe>(), &celestial_map);
+    kinetic + potential
+}
+
+fn total_momentum(world: &mut World) -> f64 {
+    let celestial_map = celestial_map(world);
+    system_momentum(world.resource::<Universe>(), &celestial_map)
+        .0
+        .length()
+}
+
+/// Runs the scenario for the requested number of steps, or for however many
+/// steps cover the requested simulation time, then writes how the energy and
+/// momentum held up and where every body ended up.
+pub fn run(options: HeadlessOptions) -> io::Result<()> {
+    let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match &options.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    });
+    if let Some(count) = options.benchmark {
+        let universe = Universe {
+            barnes_hut: options.barnes_hut,
+            integrator: options.integrator,
+            ..Universe::default()
+        };
+        return run_benchmark(&universe, count, options.steps.unwrap_or(200), &mut writer);
+    }
+
+    let mut app = headless_app(options.scenario);
+    {
+        let mut universe = app.world.resource_mut::<Universe>();
+        universe.barnes_hut = options.barnes_hut;
+        universe.integrator = options.integrator;
+    }
+    let step_seconds = app.world.resource::<Universe>().tick().0.abs() as f64;
+    let steps = match (options.steps, options.duration) {
+        (Some(steps), _) => steps,
+        (None, Some(duration)) => (duration / step_seconds).ceil() as u64,
+        (None, None) => 1000,
+    };
+
+    let energy = total_energy(&mut app.world);
+    let momentum = total_momentum(&mut app.world);
+    app.world
+        .send_event(SimulationCommand::Step(steps.min(u32::MAX as u64) as u32));
+    app.update();
+    let final_energy = total_energy(&mut app.world);
+    let final_momentum = total_momentum(&mut app.world);
+
+    let clock = app.world.resource::<SimClock>();
+    writeln!(
+        writer,
+        "# {:?}, {} steps, {:.3} simulation seconds",
+        options.scenario, clock.step_count, clock.elapsed_seconds
+    )?;
+    writeln!(
+        writer,
+        "# energy {:e} -> {:e} (relative drift {:e})",
+        energy,
+        final_energy,
+        ((final_energy - energy) / energy).abs()
+    )?;
+    writeln!(writer, "# momentum {:e} -> {:e}", momentum, final_momentum)?;
+    writeln!(writer, "name,mass,x,y,z,vx,vy,vz")?;
+    let mut bodies = app
+        .world
+        .query::<(Entity, &Celestial, &SimState, Option<&Name>)>();
+    let mut rows: Vec<_> = bodies.iter(&app.world).collect();
+    rows.sort_by_key(|(entity, ..)| *entity);
+    for (_, body, state, name) in rows {
+        let (p, v) = (state.position, state.velocity);
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            escape_csv_field(name.map_or("", |name| &name.name)),
+            body.mass,
+            p.x,
+            p.y,
+            p.z,
+            v.x,
+            v.y,
+            v.z
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        time::{create_time_channels, TimeSender},
+        utils::Instant,
+    };
+
+    use super::*;
+    use crate::physics::step_celestial_map;
+
+    /// A headless app whose frames take exactly as long as the test says.
+    struct TimedApp {
+        app: App,
+        sender: TimeSender,
+        now: Instant,
+    }
+
+    impl TimedApp {
+        fn new(scenario: Scenario) -> Self {
+            let mut app = headless_app(scenario);
+            let (sender, receiver) = create_time_channels();
+            app.insert_resource(receiver);
+            let now = app.world.resource::<Time>().last_update().unwrap();
+            Self { app, sender, now }
+        }
+
+        fn frame(&mut self, seconds: f64) {
+            self.now += Duration::from_secs_f64(seconds);
+            self.sender.0.send(self.now).unwrap();
+            self.app.update();
+        }
+    }
+
+    #[test]
+    fn prediction_matches_the_live_simulation() {
+        for integrator in IntegratorKind::ALL {
+            let mut app = headless_app(Scenario::ThreeBody);
+            {
+                let mut universe = app.world.resource_mut::<Universe>();
+                universe.integrator = integrator;
+                universe.deterministic = true;
+            }
+            let universe = app.world.resource::<Universe>().clone();
+            let mut predicted = celestial_map(&mut app.world);
+            for _ in 0..50 {
+                step_celestial_map(&mut predicted, &universe.tick(), &universe);
+            }
+            app.world.send_event(SimulationCommand::Step(50));
+            app.update();
+            let live = celestial_map(&mut app.world);
+            for (entity, body) in live.map.iter() {
+                assert_eq!(body.pos, predicted.map[entity].pos, "{:?}", integrator);
+                assert_eq!(body.vel, predicted.map[entity].vel, "{:?}", integrator);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_bodies_spawned_between_frames() {
+        let mut app = headless_app(Scenario::ThreeBody);
+        // Picks up its simulation state before the next steps run.
+        let late = app
+            .world
+            .spawn()
+            .insert(Celestial::new(1.0, Vec3::X))
+            .insert(Transform::from_xyz(50.0, 0.0, 50.0))
+            .id();
+        app.world.send_event(SimulationCommand::Step(3));
+        app.update();
+        assert_eq!(app.world.resource::<SimClock>().step_count, 3);
+
+        app.world.send_event(SimulationCommand::Step(3));
+        app.update();
+        assert_eq!(app.world.resource::<SimClock>().step_count, 6);
+        let state = app.world.get::<SimState>(late).unwrap();
+        assert_ne!(state.position.as_vec3(), Vec3::new(50.0, 0.0, 50.0));
+    }
+
+    #[test]
+    fn resuming_after_a_long_pause_runs_one_step() {
+        let mut timed = TimedApp::new(Scenario::ThreeBody);
+        let frame = {
+            let mut universe = timed.app.world.resource_mut::<Universe>();
+            universe.active = true;
+            universe.update_frequency_ms as f64 / 1000.0 + 1e-6
+        };
+        let steps = |timed: &TimedApp| timed.app.world.resource::<SimClock>().step_count;
+        timed.frame(frame);
+        timed.app.world.send_event(SimulationCommand::Pause);
+        timed.frame(frame);
+        let paused_at = steps(&timed);
+        for _ in 0..10 {
+            timed.frame(1.0);
+        }
+        assert_eq!(steps(&timed), paused_at);
+
+        timed.app.world.send_event(SimulationCommand::Resume);
+        timed.frame(frame);
+        assert_eq!(steps(&timed), paused_at + 1);
+    }
+
+    #[test]
+    fn clock_counts_requested_steps() {
+        let mut app = headless_app(Scenario::ThreeBody);
+        let dt = app.world.resource::<Universe>().tick().0 as f64;
+        app.world.send_event(SimulationCommand::Step(25));
+        app.update();
+        let clock = app.world.resource::<SimClock>();
+        assert_eq!(clock.step_count, 25);
+        assert!((clock.elapsed_seconds - 25.0 * dt).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clock_follows_real_time_steps() {
+        let mut timed = TimedApp::new(Scenario::ThreeBody);
+        let (frame, dt) = {
+            let mut universe = timed.app.world.resource_mut::<Universe>();
+            universe.active = true;
+            (
+                universe.update_frequency_ms as f64 / 1000.0,
+                universe.tick().0 as f64,
+            )
+        };
+        // Slightly long, so rounding to whole nanoseconds can't lose a step.
+        for _ in 0..10 {
+            timed.frame(frame * 1.5 + 1e-6);
+        }
+        let clock = timed.app.world.resource::<SimClock>();
+        assert_eq!(clock.step_count, 15);
+        assert!((clock.elapsed_seconds - 15.0 * dt).abs() < 1e-9);
+    }
+}
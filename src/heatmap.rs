@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use bevy::{
+    math::DVec3,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    build_celestial_maps,
+    origin::{FloatingOrigin, SimState},
+    physics::potential_at,
+    Celestial, DebugMarker, Universe,
+};
+
+// Just below the reference grid so the two don't z-fight.
+const PLANE_HEIGHT: f32 = -0.5;
+
+/// Middle of the plane in simulation space: under the render origin, on the
+/// simulation's XZ plane where the reference grid lies.
+fn plane_center(origin: &FloatingOrigin) -> DVec3 {
+    DVec3::new(origin.origin.x, 0.0, origin.origin.z)
+}
+
+#[derive(Component)]
+struct PotentialPlane;
+
+#[derive(Default)]
+struct HeatmapState {
+    since_update: Duration,
+    image: Handle<Image>,
+}
+
+/// Shallow potential is dark blue, deep wells run through teal to red.
+fn heat_color(t: f32) -> [u8; 4] {
+    let low = Vec3::new(0.05, 0.05, 0.2);
+    let mid = Vec3::new(0.1, 0.6, 0.6);
+    let high = Vec3::new(0.95, 0.2, 0.1);
+    let color = if t < 0.5 {
+        low.lerp(mid, t * 2.0)
+    } else {
+        mid.lerp(high, t * 2.0 - 1.0)
+    };
+    let [r, g, b] = (color * 255.0).to_array().map(|c| c as u8);
+    [r, g, b, 255]
+}
+
+fn potential_image(
+    universe: &Universe,
+    origin: &FloatingOrigin,
+    bodies: &Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+) -> Image {
+    let celestial_map = build_celestial_maps(bodies);
+    let resolution = universe.potential_resolution;
+    let extent = universe.grid_extent;
+    let cell = 2.0 * extent / resolution as f32;
+    let center = plane_center(origin);
+
+    // Row 0 of the texture maps to the +Z edge of the plane mesh.
+    let depths: Vec<f32> = (0..resolution)
+        .flat_map(|row| (0..resolution).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let point = center
+                + DVec3::new(
+                    (-extent + (column as f32 + 0.5) * cell) as f64,
+                    0.0,
+                    (extent - (row as f32 + 0.5) * cell) as f64,
+                );
+            // Potential wells span orders of magnitude, so color by log depth.
+            let potential = potential_at(universe, &celestial_map, point, (cell * 0.5) as f64);
+            (-potential as f32).max(f32::MIN_POSITIVE).ln()
+        })
+        .collect();
+    let (min, max) = depths.iter().fold((f32::MAX, f32::MIN), |(min, max), d| {
+        (min.min(*d), max.max(*d))
+    });
+    let data = depths
+        .iter()
+        .flat_map(|depth| {
+            let t = if max > min {
+                (depth - min) / (max - min)
+            } else {
+                0.0
+            };
+            heat_color(t)
+        })
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn update_potential_heatmap(
+    mut commands: Commands,
+    time: Res<Time>,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut state: Local<HeatmapState>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut plane: Query<(Entity, &mut Transform), (With<PotentialPlane>, Without<Celestial>)>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+) {
+    if !universe.show_potential {
+        for (entity, _) in plane.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    state.since_update += time.delta();
+    let existing = plane.get_single_mut().ok();
+    if existing.is_some()
+        && state.since_update < Duration::from_millis(universe.potential_update_ms)
+    {
+        return;
+    }
+    state.since_update = Duration::ZERO;
+
+    let image = potential_image(&universe, &origin, &bodies);
+    let scale = Vec3::splat(2.0 * universe.grid_extent);
+    let translation = origin.to_render(plane_center(&origin)) + Vec3::Y * PLANE_HEIGHT;
+    match existing {
+        Some((_, mut transform)) => {
+            if let Some(current) = images.get_mut(&state.image) {
+                *current = image;
+            }
+            transform.scale = scale;
+            transform.translation = translation;
+        }
+        None => {
+            state.image = images.add(image);
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Plane { size: 1.0 })),
+                    material: materials.add(StandardMaterial {
+                        base_color_texture: Some(state.image.clone()),
+                        unlit: true,
+                        ..Default::default()
+                    }),
+                    transform: Transform::from_translation(translation).with_scale(scale),
+                    ..Default::default()
+                })
+                .insert(PotentialPlane)
+                // Shifted with everything else when the origin moves.
+                .insert(DebugMarker);
+        }
+    }
+}
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_potential_heatmap);
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    orbits::{hill_radius, OrbitalElements},
+    overlay::draw_wire_sphere,
+    soi::SphereOfInfluence,
+    Celestial, InspectTarget,
+};
+
+const HILL_SPHERE_COLOR: Color = Color::rgba(0.4, 1.0, 0.5, 0.6);
+
+/// Draws the body's Hill sphere, inside which a moon can stay bound to it.
+#[derive(Component)]
+pub struct ShowHillSphere;
+
+/// Shows or hides the selected body's Hill sphere with H.
+fn toggle_hill_sphere(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    bodies: Query<Option<&ShowHillSphere>, With<Celestial>>,
+) {
+    if !keys.just_pressed(KeyCode::H) {
+        return;
+    }
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    match bodies.get(target) {
+        Ok(Some(_)) => {
+            commands.entity(target).remove::<ShowHillSphere>();
+        }
+        Ok(None) => {
+            commands.entity(target).insert(ShowHillSphere);
+        }
+        Err(_) => {}
+    }
+}
+
+/// Only bodies on a bound orbit around a dominant body have a Hill sphere.
+fn draw_hill_spheres(
+    mut lines: ResMut<DebugLines>,
+    bodies: Query<
+        (&Celestial, &Transform, &OrbitalElements, &SphereOfInfluence),
+        With<ShowHillSphere>,
+    >,
+    attractors: Query<&Celestial>,
+) {
+    for (body, transform, elements, sphere) in bodies.iter() {
+        let attractor = match sphere
+            .dominant
+            .and_then(|attractor| attractors.get(attractor).ok())
+        {
+            Some(attractor) => attractor,
+            None => continue,
+        };
+        if elements.eccentricity >= 1.0 {
+            continue;
+        }
+        let radius = hill_radius(
+            elements.semi_major_axis,
+            elements.eccentricity,
+            body.mass as f64,
+            attractor.mass as f64,
+        );
+        draw_wire_sphere(
+            &mut lines,
+            transform.translation,
+            radius as f32,
+            HILL_SPHERE_COLOR,
+        );
+    }
+}
+
+pub struct HillSpherePlugin;
+
+impl Plugin for HillSpherePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_hill_sphere)
+            .add_system(draw_hill_spheres);
+    }
+}
@@ -0,0 +1,202 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::FloatingOrigin,
+    overlay::{draw_wire_sphere, screen_position},
+    prediction::PredictedPath,
+    units::{format_duration, Units},
+    Name, Radius, SimClock, Universe,
+};
+
+const IMPACT_COLOR: Color = Color::RED;
+/// Most impacts listed in the warning, soonest first.
+const MAX_LISTED: usize = 3;
+
+/// Two bodies whose predictions touch, at the first step they do.
+pub struct PredictedImpact {
+    pub a: Entity,
+    pub b: Entity,
+    /// Index into both bodies' `PredictedPath::points`.
+    pub index: usize,
+    /// Simulation time of the impact.
+    pub time: f64,
+}
+
+/// Every impact in the current predictions, soonest first.
+#[derive(Default)]
+pub struct PredictedImpacts(pub Vec<PredictedImpact>);
+
+/// First step at which two paths, sampled at the same steps, come within
+/// `reach` of each other. Only the sampled steps are checked, so a fast
+/// grazing pass with a large `debug_stride` can slip between them.
+pub fn first_contact(a: &[DVec3], b: &[DVec3], reach: f64) -> Option<usize> {
+    a.iter().zip(b).position(|(a, b)| a.distance(*b) < reach)
+}
+
+/// Checks every pair of predictions against the sum of their radii whenever
+/// the predictions change.
+fn find_predicted_impacts(
+    universe: Res<Universe>,
+    changed: Query<(), Changed<PredictedPath>>,
+    removed: RemovedComponents<PredictedPath>,
+    paths: Query<(Entity, &PredictedPath, &Radius)>,
+    mut impacts: ResMut<PredictedImpacts>,
+    mut warned: Local<bool>,
+) {
+    let toggled = universe.warn_impacts != *warned;
+    *warned = universe.warn_impacts;
+    if changed.is_empty() && removed.iter().next().is_none() && !toggled {
+        return;
+    }
+    impacts.0.clear();
+    if !universe.warn_impacts {
+        return;
+    }
+    let paths: Vec<(Entity, &PredictedPath, &Radius)> = paths.iter().collect();
+    for (i, (a, path_a, radius_a)) in paths.iter().enumerate() {
+        for (b, path_b, radius_b) in &paths[i + 1..] {
+            let reach = (radius_a.0 + radius_b.0) as f64;
+            if let Some(index) = first_contact(&path_a.positions, &path_b.positions, reach) {
+                impacts.0.push(PredictedImpact {
+                    a: *a,
+                    b: *b,
+                    index,
+                    time: path_a.time_at(index),
+                });
+            }
+        }
+    }
+    impacts.0.sort_by_key(|impact| impact.index);
+}
+
+/// Simulation time until `time`, with its real-world length when the
+/// scenario is in physical units.
+fn describe_remaining(units: &Units, remaining: f64) -> String {
+    if *units == Units::default() {
+        format!("{:.2}", remaining)
+    } else {
+        format!(
+            "{:.2} ({})",
+            remaining,
+            format_duration(units.time_to_si(remaining))
+        )
+    }
+}
+
+/// Reddens the last stretch of both paths before contact, marks it, and
+/// lists the soonest impacts at the top of the screen.
+fn draw_predicted_impacts(
+    universe: Res<Universe>,
+    units: Res<Units>,
+    clock: Res<SimClock>,
+    origin: Res<FloatingOrigin>,
+    impacts: Res<PredictedImpacts>,
+    mut lines: ResMut<DebugLines>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    paths: Query<(&PredictedPath, &Radius)>,
+    names: Query<&Name>,
+) {
+    let sign = universe.direction.sign() as f64;
+    // A prediction that isn't refreshed while running goes stale, and its
+    // impacts can fall into the past.
+    let upcoming: Vec<(&PredictedImpact, f64)> = impacts
+        .0
+        .iter()
+        .map(|impact| (impact, (impact.time - clock.elapsed_seconds) * sign))
+        .filter(|(_, remaining)| *remaining >= 0.0)
+        .collect();
+    if upcoming.is_empty() {
+        return;
+    }
+    let camera = camera.get_single().ok();
+    let name_of = |entity: Entity| {
+        names
+            .get(entity)
+            .map(|name| name.name.clone())
+            .unwrap_or_else(|_| format!("{:?}", entity))
+    };
+    let ctx = egui_context.ctx_mut();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    for (impact, remaining) in upcoming.iter() {
+        let ((path_a, radius_a), (path_b, radius_b)) = match paths.get_many([impact.a, impact.b]) {
+            Ok([a, b]) => (a, b),
+            Err(_) => continue,
+        };
+        for path in [path_a, path_b] {
+            let end = (impact.index + 1).min(path.points.len());
+            let approach = &path.points[impact.index.saturating_sub(5)..end];
+            for pair in approach.windows(2) {
+                lines.line_colored(
+                    origin.to_render(pair[0]),
+                    origin.to_render(pair[1]),
+                    0.0,
+                    IMPACT_COLOR,
+                );
+            }
+        }
+        let contact = match (
+            path_a.points.get(impact.index),
+            path_b.points.get(impact.index),
+        ) {
+            (Some(a), Some(b)) => origin.to_render(a.lerp(*b, 0.5)),
+            _ => continue,
+        };
+        draw_wire_sphere(
+            &mut lines,
+            contact,
+            radius_a.0.max(radius_b.0),
+            IMPACT_COLOR,
+        );
+        let screen =
+            camera.and_then(|(camera, transform)| screen_position(camera, transform, contact));
+        if let Some(screen) = screen {
+            painter.text(
+                screen + egui::vec2(6.0, -6.0),
+                Align2::LEFT_BOTTOM,
+                format!("impact in {}", describe_remaining(&units, *remaining)),
+                FontId::proportional(12.0),
+                egui::Color32::RED,
+            );
+        }
+    }
+
+    egui::Area::new("impact_warning")
+        .anchor(Align2::CENTER_TOP, [0.0, 10.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            for (impact, remaining) in upcoming.iter().take(MAX_LISTED) {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!(
+                        "{} and {} collide in {}",
+                        name_of(impact.a),
+                        name_of(impact.b),
+                        describe_remaining(&units, *remaining)
+                    ),
+                );
+            }
+            if upcoming.len() > MAX_LISTED {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("and {} more", upcoming.len() - MAX_LISTED),
+                );
+            }
+        });
+}
+
+pub struct ImpactPlugin;
+
+impl Plugin for ImpactPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PredictedImpacts>()
+            .add_system(find_predicted_impacts)
+            .add_system(draw_predicted_impacts.after(find_predicted_impacts));
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::{math::DVec3, prelude::*, transform::TransformSystem};
+
+use crate::{
+    origin::{adopt_external_edits, FloatingOrigin, SimState},
+    step_universe, DebugMarker, StepBudget, Universe, UniverseStep,
+};
+
+/// A body's simulation position either side of the last step, and what was
+/// drawn in between.
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: DVec3,
+    current: DVec3,
+    /// The blended translation written for rendering, put back to the real
+    /// one at the start of the next frame.
+    shown: Option<Vec3>,
+}
+
+fn attach_interpolated(
+    mut commands: Commands,
+    bodies: Query<(Entity, &SimState), (Without<Interpolated>, Without<DebugMarker>)>,
+) {
+    for (entity, state) in bodies.iter() {
+        commands.entity(entity).insert(Interpolated {
+            previous: state.position,
+            current: state.position,
+            shown: None,
+        });
+    }
+}
+
+fn remember_previous(mut bodies: Query<(&SimState, &mut Interpolated)>) {
+    for (state, mut interpolated) in bodies.iter_mut() {
+        interpolated.previous = state.position;
+    }
+}
+
+fn remember_current(mut bodies: Query<(&SimState, &mut Interpolated)>) {
+    for (state, mut interpolated) in bodies.iter_mut() {
+        interpolated.current = state.position;
+    }
+}
+
+/// Only touches transforms that still show the simulated position. One that
+/// was moved this frame, by dragging say, is left for the origin to adopt,
+/// and a body moved without stepping (a rewind or a checkpoint) is drawn
+/// where it is rather than blended from where it was.
+fn interpolate_transforms(
+    universe: Res<Universe>,
+    budget: Res<StepBudget>,
+    origin: Res<FloatingOrigin>,
+    mut bodies: Query<(&SimState, &mut Interpolated, &mut Transform)>,
+) {
+    if !universe.interpolate {
+        return;
+    }
+    for (state, mut interpolated, mut transform) in bodies.iter_mut() {
+        if state.position != interpolated.current
+            || transform.translation != origin.to_render(state.position)
+        {
+            continue;
+        }
+        let blended = interpolated
+            .previous
+            .lerp(interpolated.current, budget.step_fraction);
+        let shown = origin.to_render(blended);
+        transform.translation = shown;
+        interpolated.shown = Some(shown);
+    }
+}
+
+/// Everything else reads the transform as the f32 view of the simulation
+/// state, so the blended position is undone before anything runs.
+fn restore_transforms(
+    origin: Res<FloatingOrigin>,
+    mut bodies: Query<(&SimState, &mut Interpolated, &mut Transform)>,
+) {
+    for (state, mut interpolated, mut transform) in bodies.iter_mut() {
+        if let Some(shown) = interpolated.shown.take() {
+            if transform.translation == shown {
+                transform.translation = origin.to_render(state.position);
+            }
+        }
+    }
+}
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, attach_interpolated)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                restore_transforms.before(adopt_external_edits),
+            )
+            .add_system(
+                remember_previous
+                    .with_run_criteria(UniverseStep)
+                    .before(step_universe),
+            )
+            .add_system(
+                remember_current
+                    .with_run_criteria(UniverseStep)
+                    .after(step_universe),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
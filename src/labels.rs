@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+
+use crate::{overlay::screen_position, Celestial, DebugMarker, Name, Radius, Universe};
+
+/// F4 shows or hides the name labels.
+fn toggle_labels(keys: Res<Input<KeyCode>>, mut universe: ResMut<Universe>) {
+    if keys.just_pressed(KeyCode::F4) {
+        universe.show_labels = !universe.show_labels;
+    }
+}
+
+/// Each body's name just above it on screen. Labels fade out over the last
+/// half of `label_fade_distance`, so a crowded system far off doesn't turn
+/// into a pile of text.
+fn draw_labels(
+    universe: Res<Universe>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    bodies: Query<(&Name, &GlobalTransform, &Radius), (With<Celestial>, Without<DebugMarker>)>,
+) {
+    if !universe.show_labels {
+        return;
+    }
+    let (camera, camera_transform) = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let up = camera_transform.up();
+    let fade_distance = universe.label_fade_distance.max(1.0);
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for (name, transform, radius) in bodies.iter() {
+        let center = transform.translation();
+        let distance = center.distance(camera_transform.translation());
+        let alpha = (2.0 - 2.0 * distance / fade_distance).clamp(0.0, 1.0);
+        if alpha == 0.0 {
+            continue;
+        }
+        let above = center + up * radius.0 * 1.2;
+        if let Some(screen) = screen_position(camera, camera_transform, above) {
+            painter.text(
+                screen,
+                Align2::CENTER_BOTTOM,
+                &name.name,
+                FontId::proportional(13.0),
+                egui::Rgba::from_white_alpha(alpha).into(),
+            );
+        }
+    }
+}
+
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_labels)
+            .add_system(draw_labels.after(toggle_labels));
+    }
+}
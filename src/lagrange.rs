@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::{
+    build_celestial_maps,
+    orbits::{dominant_attractor, lagrange_points},
+    origin::{FloatingOrigin, SimState},
+    step_universe, Celestial, DebugMarker, InspectTarget, Name, Universe,
+};
+
+const MARKER_COLOR: Color = Color::rgb(1.0, 0.2, 0.8);
+
+/// Marks one of the five Lagrange points of the selected body and the body it
+/// orbits. Markers have no `Celestial` and aren't pickable, so neither the
+/// simulation nor selection ever sees them. M or the inspector toggles them.
+#[derive(Component)]
+struct LagrangeMarker(usize);
+
+fn update_lagrange_markers(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    inspector: Res<InspectTarget>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    mut markers: Query<(Entity, &LagrangeMarker, &mut Transform), Without<Celestial>>,
+) {
+    let points = universe
+        .show_lagrange_points
+        .then(|| inspector.target)
+        .flatten()
+        .and_then(|target| {
+            let celestial_map = build_celestial_maps(&bodies);
+            let attractor = dominant_attractor(&celestial_map, target)?;
+            let points =
+                lagrange_points(&celestial_map.map[&attractor], &celestial_map.map[&target]);
+            Some(points.map(|point| origin.to_render(point)))
+        });
+    let points = match points {
+        Some(points) => points,
+        None => {
+            for (entity, ..) in markers.iter() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    };
+
+    if markers.is_empty() {
+        let mesh = meshes.add(Mesh::from(shape::Icosphere {
+            radius: 1.5,
+            subdivisions: 1,
+        }));
+        let material = materials.add(StandardMaterial {
+            base_color: MARKER_COLOR,
+            unlit: true,
+            ..Default::default()
+        });
+        for (i, point) in points.iter().enumerate() {
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(*point),
+                    ..Default::default()
+                })
+                .insert(Name {
+                    name: format!("L{}", i + 1),
+                })
+                .insert(LagrangeMarker(i));
+        }
+        return;
+    }
+    for (_, marker, mut transform) in markers.iter_mut() {
+        transform.translation = points[marker.0];
+    }
+}
+
+pub struct LagrangePlugin;
+
+impl Plugin for LagrangePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_lagrange_markers.after(step_universe));
+    }
+}
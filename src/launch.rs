@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    scenario::{spawn_body, BodySpec},
+    state::outside_menu,
+    Universe,
+};
+
+#[derive(Inspectable)]
+pub struct LaunchSettings {
+    #[inspectable(min = 0.0)]
+    speed: f32,
+    #[inspectable(min = 0.001)]
+    mass: f32,
+    #[inspectable(min = 0.1)]
+    radius: f32,
+    color: Color,
+}
+
+impl Default for LaunchSettings {
+    fn default() -> Self {
+        Self {
+            speed: 50.0,
+            mass: 1.0,
+            radius: 1.5,
+            color: Color::WHITE,
+        }
+    }
+}
+
+/// Fires a new body from the camera along its view direction with L.
+fn launch_from_camera(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    universe: Res<Universe>,
+    settings: Res<LaunchSettings>,
+    mut launched: Local<u32>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera: Query<&Transform, With<FlyCam>>,
+) {
+    if !keys.just_pressed(KeyCode::L) {
+        return;
+    }
+    let camera = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    *launched += 1;
+    let forward = camera.forward();
+    spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &universe,
+        BodySpec {
+            name: format!("Projectile {}", *launched),
+            mass: settings.mass,
+            radius: settings.radius,
+            color: settings.color,
+            // Start clear of the near plane so it's visible straight away.
+            position: camera.translation + forward * settings.radius * 4.0,
+            velocity: forward * settings.speed,
+            parent: None,
+        },
+    );
+}
+
+pub struct LaunchPlugin;
+
+impl Plugin for LaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaunchSettings>()
+            .add_system(launch_from_camera.with_run_criteria(outside_menu));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::AssetPlugin;
+
+    use crate::{Celestial, Name};
+
+    #[test]
+    fn launches_along_the_view_direction() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_plugin(LaunchPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .insert_resource(Universe::default())
+            .init_resource::<Input<KeyCode>>();
+        let camera = Transform::from_xyz(10.0, 5.0, 0.0).looking_at(Vec3::ZERO, Vec3::Y);
+        app.world.spawn().insert(camera).insert(FlyCam);
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::L);
+        app.update();
+
+        let mut launched = app.world.query::<(&Name, &Celestial, &Transform)>();
+        let (name, body, transform) = launched.single(&app.world);
+        let settings = LaunchSettings::default();
+        let forward = camera.forward();
+        assert_eq!(name.name, "Projectile 1");
+        assert!(transform
+            .translation
+            .abs_diff_eq(camera.translation + forward * settings.radius * 4.0, 1e-4));
+        assert!(body.velocity.abs_diff_eq(forward * settings.speed, 1e-4));
+    }
+}
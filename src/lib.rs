@@ -0,0 +1,1248 @@
+// Bevy code commonly triggers these lints and they may be important signals
+// about code quality. They are sometimes hard to avoid though, and the CI
+// workflow treats them as errors, so this allows them throughout the project.
+// Feel free to delete this line.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod appearance;
+mod approach;
+mod apsides;
+mod atmosphere;
+mod background;
+pub mod barnes_hut;
+mod barycenter;
+pub mod benchmark;
+mod boundary;
+mod camera;
+mod checkpoint;
+mod collision;
+mod crossings;
+mod details;
+mod diagnostics;
+mod drag;
+mod export;
+mod field;
+mod frames;
+mod gpu;
+pub mod headless;
+mod heatmap;
+mod hill;
+mod impact;
+mod interpolation;
+mod labels;
+mod lagrange;
+mod launch;
+mod maneuver;
+mod measure;
+mod orbits;
+mod origin;
+mod overlay;
+mod particles;
+pub mod physics;
+mod pin;
+mod prediction;
+mod recording;
+mod rewind;
+mod scenario;
+mod screenshot;
+mod selection;
+mod soi;
+mod spacecraft;
+mod spin;
+mod state;
+mod tidal;
+mod timeline;
+mod trails;
+mod units;
+mod vectors;
+mod warp;
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use appearance::{AppearancePlugin, LineColoring};
+use approach::ClosestApproachPlugin;
+use apsides::{find_apsides, ApsidesPlugin, Apsis};
+use atmosphere::{mark_atmospheres, Atmosphere, AtmospherePlugin};
+use background::BackgroundField;
+use barycenter::BarycenterPlugin;
+use bevy::{
+    ecs::schedule::{RunCriteriaDescriptorCoercion, ShouldRun},
+    math::DVec3,
+    prelude::*,
+    transform::TransformSystem,
+};
+use bevy_flycam::{FlyCam, NoCameraPlayerPlugin};
+use bevy_inspector_egui::{
+    widgets::{InspectableButton, ResourceInspector},
+    Inspectable, InspectorPlugin, RegisterInspectable,
+};
+use bevy_mod_picking::{DefaultPickingPlugins, PickingCameraBundle};
+use bevy_prototype_debug_lines::DebugLinesPlugin;
+use boundary::{Boundary, BoundaryPlugin};
+use camera::{CameraControlPlugin, CameraSettings};
+use checkpoint::CheckpointPlugin;
+use collision::{CollisionPlugin, CollisionResponse};
+use crossings::SoiCrossingPlugin;
+use details::DetailsPlugin;
+use diagnostics::{ConservationStats, DiagnosticsPlugin};
+use drag::{DragPlugin, Held};
+use export::{
+    export_trajectory, ExportPlugin, ExportSource, ExportTrajectoryEvent, TrajectoryExport,
+    TrajectorySample,
+};
+use field::FieldPlugin;
+use frames::{in_parent_frame, FramePlugin, ParentBody, ReferenceFrame};
+use gpu::GpuForcesPlugin;
+use heatmap::HeatmapPlugin;
+use hill::HillSpherePlugin;
+use impact::ImpactPlugin;
+use interpolation::InterpolationPlugin;
+use labels::LabelPlugin;
+use lagrange::LagrangePlugin;
+use launch::{LaunchPlugin, LaunchSettings};
+use maneuver::{apply_maneuvers, ManeuverNode, ManeuverPlugin, ManeuverPreview};
+use measure::MeasurePlugin;
+use orbits::{dominant_attractor, OrbitalElementsPlugin};
+use origin::{FloatingOrigin, FloatingOriginPlugin, SimState};
+use overlay::OverlayPlugin;
+use particles::{TestParticle, TestParticlePlugin};
+use physics::{
+    step_celestial_map, step_patched_conics, CelestialBundle, CelestialMap, IntegratorKind,
+    TimeDirection,
+};
+use pin::{mark_pinned, PinPlugin, Pinned};
+use prediction::{PredictedPath, PredictionPlugin};
+use recording::{
+    RecordingPlugin, RecordingSettings, Replay, ToggleRecordingEvent, ToggleReplayEvent,
+};
+use rewind::{RewindBuffer, RewindPlugin};
+use scenario::{spawn_scenario, LoadScenarioEvent, Scenario};
+use screenshot::{ScreenshotPlugin, ScreenshotSettings};
+use selection::SelectionPlugin;
+use soi::SphereOfInfluencePlugin;
+use spacecraft::SpacecraftPlugin;
+use spin::SpinPlugin;
+use state::{outside_menu, AppState, AppStatePlugin};
+use tidal::TidalPlugin;
+use timeline::{LoadTimelineEvent, TimelinePlugin, TimelineSettings};
+use trails::TrailPlugin;
+use units::Units;
+use vectors::VectorPlugin;
+use warp::{WarpPlugin, WarpSettings};
+
+#[derive(Inspectable, Component)]
+pub struct Name {
+    pub name: String,
+}
+
+#[derive(Inspectable, Default)]
+pub struct InspectTarget {
+    target: Option<Entity>,
+}
+
+/// Tags helper entities that sit in the scene but aren't bodies, so body
+/// queries can leave them out. Predictions are drawn as line strips through
+/// `DebugLines` rather than as spawned spheres, so they add no entities or
+/// mesh assets however often they're refreshed.
+#[derive(Component)]
+pub struct DebugMarker;
+
+#[derive(Inspectable, Component, Clone)]
+pub struct Celestial {
+    mass: f32,
+    velocity: Vec3,
+    charge: f32,
+    #[inspectable(min = 0.0001)]
+    density: f32,
+}
+
+impl Celestial {
+    pub fn new(mass: f32, velocity: Vec3) -> Self {
+        Self {
+            mass,
+            velocity,
+            charge: 0.0,
+            density: 1.0,
+        }
+    }
+
+    /// Density that gives a body of `mass` the requested `radius`.
+    pub fn density_for(mass: f32, radius: f32) -> f32 {
+        mass / sphere_volume(radius)
+    }
+
+    /// Radius of a sphere with this body's mass and density.
+    pub fn radius(&self) -> f32 {
+        (self.mass.max(0.0) / self.density.max(f32::EPSILON) / (4.0 / 3.0 * PI)).cbrt()
+    }
+}
+
+pub fn sphere_volume(radius: f32) -> f32 {
+    4.0 / 3.0 * PI * radius.powi(3)
+}
+
+/// Physical radius of a body, kept in line with its mass and density. Body
+/// meshes are unit spheres scaled by it.
+#[derive(Component, Clone, Copy)]
+pub struct Radius(pub f32);
+
+fn update_radius(
+    mut commands: Commands,
+    mut bodies: Query<
+        (Entity, &Celestial, Option<&mut Radius>, &mut Transform),
+        (
+            Changed<Celestial>,
+            Without<DebugMarker>,
+            Without<TestParticle>,
+        ),
+    >,
+) {
+    for (entity, body, radius, mut transform) in bodies.iter_mut() {
+        let value = body.radius();
+        match radius {
+            Some(mut radius) if radius.0 != value => radius.0 = value,
+            Some(_) => continue,
+            None => {
+                commands.entity(entity).insert(Radius(value));
+            }
+        }
+        transform.scale = Vec3::splat(value);
+    }
+}
+
+#[derive(Inspectable, Clone, PartialEq)]
+pub struct Universe {
+    pub active: bool,
+    pub gravitational_constant: f32,
+    #[inspectable(min = 0.0)]
+    pub softening: f32,
+    coulomb_constant: f32,
+    background: BackgroundField,
+    background_mass: f32,
+    background_acceleration: Vec3,
+    #[inspectable(min = 0.0)]
+    disk_scale_length: f32,
+    #[inspectable(min = 0.001)]
+    disk_scale_height: f32,
+    /// Drag coefficient of every body flying through an atmosphere.
+    #[inspectable(min = 0.0)]
+    drag_coefficient: f32,
+    post_newtonian: bool,
+    #[inspectable(min = 1.0)]
+    speed_of_light: f32,
+    /// Limits the post-Newtonian correction to bodies this close to the
+    /// heaviest one. Unset, every pair gets it.
+    #[inspectable(replacement = default_post_newtonian_range as fn() -> f32)]
+    post_newtonian_range: Option<f32>,
+    /// Real time per simulation step. A slow frame runs several steps to
+    /// catch up.
+    #[inspectable(min = 1, max = 1000)]
+    update_frequency_ms: u64,
+    /// Runs this many times as many steps per second of real time, each the
+    /// same length, so speeding up doesn't cost accuracy. Comma and period
+    /// step through `TIME_SCALES`.
+    #[inspectable(min = 0.01, max = 1000.0)]
+    time_scale: f32,
+    /// Most steps a single frame will run to catch up with real time.
+    #[inspectable(min = 1, max = 10000)]
+    max_steps_per_frame: u32,
+    /// Draws bodies part way between the last two steps, by how far real time
+    /// has got towards the next one, so motion stays smooth at any frame rate.
+    /// Everything is drawn up to one step behind the simulation.
+    interpolate: bool,
+    #[inspectable(min = 1, max = 1000)]
+    simulation_step_ms: u64,
+    /// Steps predicted ahead when `prediction_horizon` is unset.
+    #[inspectable(min = 1, max = 5000)]
+    debug_steps: u32,
+    /// Keeps only every this many predicted steps, so a long prediction
+    /// doesn't turn into tens of thousands of line segments.
+    #[inspectable(min = 1, max = 1000)]
+    debug_stride: u32,
+    /// Predicts this far ahead in simulation time instead of `debug_steps`
+    /// steps.
+    #[inspectable(replacement = default_prediction_horizon as fn() -> f32)]
+    prediction_horizon: Option<f32>,
+    /// How far the end of the prediction fades towards the background, so
+    /// the direction of travel reads at a glance.
+    #[inspectable(min = 0.0, max = 1.0)]
+    prediction_fade: f32,
+    /// Predicts again after every step while the simulation runs. Off, the
+    /// prediction only follows edits made while paused.
+    live_prediction: bool,
+    #[inspectable(replacement = default_max_speed as fn() -> f32)]
+    max_speed: Option<f32>,
+    planar: bool,
+    integrator: IntegratorKind,
+    /// Run the simulation backwards. V flips it.
+    direction: TimeDirection,
+    #[inspectable(min = 1, max = 100)]
+    substeps: u32,
+    /// Visit bodies in a stable order so identical runs produce identical
+    /// trajectories.
+    deterministic: bool,
+    adaptive_timestep: bool,
+    #[inspectable(min = 0.001)]
+    adaptive_distance: f32,
+    #[inspectable(min = 0.001, max = 1000.0)]
+    min_dt_ms: f32,
+    #[inspectable(min = 0.001, max = 1000.0)]
+    max_dt_ms: f32,
+    /// Move bodies with no other body in their sphere of influence along
+    /// their Kepler orbit around the body dominating them, instead of
+    /// integrating them. Everything else stays N-body.
+    kepler_propagation: bool,
+    barnes_hut: bool,
+    #[inspectable(min = 0.0, max = 2.0)]
+    barnes_hut_theta: f32,
+    /// Sum gravity in a compute shader. Ignored while Barnes-Hut is on, and
+    /// falls back to the CPU when the device has no compute support.
+    gpu_forces: bool,
+    zero_net_momentum: bool,
+    recenter_barycenter: bool,
+    /// The reference grid and axes. F2 toggles them.
+    show_grid: bool,
+    /// Widens the grid spacing by powers of ten as the camera moves away
+    /// from it, and keeps the grid under the camera. `grid_spacing` is then
+    /// the finest it gets.
+    adaptive_grid: bool,
+    #[inspectable(min = 1.0)]
+    grid_spacing: f32,
+    #[inspectable(min = 1.0)]
+    grid_extent: f32,
+    collision_response: CollisionResponse,
+    #[inspectable(min = 0.0, max = 1.0)]
+    restitution: f32,
+    /// Break bodies apart once they come inside the Roche limit of a heavier one.
+    tidal_breakup: bool,
+    #[inspectable(min = 2, max = 16)]
+    fragment_count: u32,
+    auto_color: bool,
+    /// Also the gradient `line_coloring` uses.
+    color_low: Color,
+    color_mid: Color,
+    color_high: Color,
+    #[inspectable(min = 0.001)]
+    velocity_drag_scale: f32,
+    scenario: Scenario,
+    line_coloring: LineColoring,
+    #[inspectable(max = 5000)]
+    trail_length: usize,
+    /// Simulation steps between trail points. Longer intervals reach further
+    /// back for the same `trail_length`.
+    #[inspectable(min = 1, max = 1000)]
+    trail_interval: u64,
+    /// How many past steps backspace can rewind through.
+    #[inspectable(min = 1, max = 10000)]
+    rewind_history: usize,
+    /// Simulation time each press of backspace goes back.
+    #[inspectable(min = 0.0)]
+    rewind_seconds: f32,
+    boundary: Boundary,
+    #[inspectable(min = 1.0)]
+    boundary_radius: f32,
+    floor_height: f32,
+    #[inspectable(min = 0.0, max = 1.0)]
+    boundary_restitution: f32,
+    show_boundary: bool,
+    show_potential: bool,
+    #[inspectable(min = 8, max = 512)]
+    potential_resolution: u32,
+    #[inspectable(min = 10, max = 5000)]
+    potential_update_ms: u64,
+    show_lagrange_points: bool,
+    /// Mark where each predicted path comes closest to and goes farthest
+    /// from the body it orbits.
+    show_apsides: bool,
+    /// Mark where the predictions of the selected body and the one selected
+    /// before it come closest.
+    show_closest_approach: bool,
+    /// Warn about bodies whose predictions collide, with the time left until
+    /// they do.
+    warn_impacts: bool,
+    /// Mark where predicted paths go into or out of another body's sphere of
+    /// influence.
+    show_soi_crossings: bool,
+    /// Each body's name above it. F4 toggles them.
+    show_labels: bool,
+    /// Labels start fading at half this distance from the camera and are
+    /// gone by it.
+    #[inspectable(min = 1.0)]
+    label_fade_distance: f32,
+    /// Arrows along every body's velocity. O toggles them.
+    show_velocity: bool,
+    /// Arrows along every body's net acceleration. Z toggles them.
+    show_acceleration: bool,
+    /// Length of a vector arrow per unit of the log of its magnitude.
+    #[inspectable(min = 0.1)]
+    vector_scale: f32,
+    /// Accelerations are multiplied by this before taking the log, since
+    /// they're usually far smaller than speeds.
+    #[inspectable(min = 0.0)]
+    acceleration_scale: f32,
+    /// Arrows of the gravitational field on a grid around the camera. F3
+    /// toggles them.
+    show_field: bool,
+    /// Samples the field through the space around the camera rather than
+    /// just on the XZ plane.
+    field_3d: bool,
+    /// Arrows along each side of the field grid.
+    #[inspectable(min = 1, max = 41)]
+    field_resolution: u32,
+    #[inspectable(min = 0.1)]
+    field_spacing: f32,
+    /// Draw the prediction of bodies with a parent relative to where the
+    /// parent is now, rather than where it will be.
+    predict_in_parent_frame: bool,
+    /// Predict trajectories with each body only feeling the body whose sphere
+    /// of influence it is in. Much faster, less accurate for close encounters.
+    patched_conic_prediction: bool,
+    /// How far the camera may wander from the render origin before everything
+    /// is re-centered on it.
+    #[inspectable(min = 10.0)]
+    origin_rebase_distance: f32,
+    /// How many test particles Y scatters, and how far out.
+    #[inspectable(min = 1, max = 10000)]
+    tracer_count: u32,
+    #[inspectable(min = 1.0)]
+    tracer_spread: f32,
+}
+
+impl Universe {
+    /// Projects `v` onto the XZ plane when planar mode is enabled.
+    fn constrain(&self, v: Vec3) -> Vec3 {
+        self.constrain_f64(v.as_dvec3()).as_vec3()
+    }
+
+    fn constrain_f64(&self, v: DVec3) -> DVec3 {
+        if self.planar {
+            DVec3::new(v.x, 0.0, v.z)
+        } else {
+            v
+        }
+    }
+
+    /// One simulation step, negated while time runs in reverse.
+    fn tick(&self) -> UniverseTick {
+        UniverseTick(self.direction.sign() * self.simulation_step_ms as f32 / 1000.0)
+    }
+
+    /// Steps the prediction runs, rounded up to a whole number of strides.
+    fn prediction_steps(&self) -> u32 {
+        let stride = self.debug_stride.max(1);
+        let steps = match self.prediction_horizon {
+            Some(horizon) => {
+                let step = self.simulation_step_ms as f32 / 1000.0;
+                (horizon.max(0.0) / step).ceil() as u32
+            }
+            None => self.debug_steps,
+        };
+        steps.div_ceil(stride).max(1) * stride
+    }
+}
+
+fn default_max_speed() -> f32 {
+    100.0
+}
+
+fn default_prediction_horizon() -> f32 {
+    100.0
+}
+
+fn default_post_newtonian_range() -> f32 {
+    100.0
+}
+
+/// Simulated time since the scenario was loaded, in simulation units. It only
+/// moves when the simulation steps, so pauses, the time scale and steps
+/// dropped to the per-frame cap are all accounted for.
+#[derive(Inspectable, Default, Clone)]
+pub struct SimClock {
+    elapsed_seconds: f64,
+    step_count: u64,
+}
+
+impl SimClock {
+    /// Reversed ticks have a negative `dt` and count the clock back down.
+    fn advance(&mut self, dt: f32) {
+        self.elapsed_seconds += dt as f64;
+        self.step_count = if dt < 0.0 {
+            self.step_count.saturating_sub(1)
+        } else {
+            self.step_count + 1
+        };
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Inspectable, Default)]
+struct UniverseInspector {
+    universe: ResourceInspector<Universe>,
+    #[inspectable(label = "", text = "Load scenario")]
+    load_scenario: InspectableButton<LoadScenarioEvent>,
+    #[inspectable(label = "", text = "Step")]
+    step: InspectableButton<SimulationCommand>,
+    #[inspectable(read_only)]
+    clock: ResourceInspector<SimClock>,
+    #[inspectable(read_only)]
+    units: ResourceInspector<Units>,
+    #[inspectable(read_only)]
+    step_budget: ResourceInspector<StepBudget>,
+    #[inspectable(read_only)]
+    conservation: ResourceInspector<ConservationStats>,
+    export: ResourceInspector<TrajectoryExport>,
+    #[inspectable(label = "", text = "Export trajectory")]
+    export_trajectory: InspectableButton<ExportTrajectoryEvent>,
+    recording: ResourceInspector<RecordingSettings>,
+    #[inspectable(label = "", text = "Start/stop recording")]
+    toggle_recording: InspectableButton<ToggleRecordingEvent>,
+    #[inspectable(label = "", text = "Start/stop replay")]
+    toggle_replay: InspectableButton<ToggleReplayEvent>,
+    timeline: ResourceInspector<TimelineSettings>,
+    #[inspectable(label = "", text = "Reload timeline")]
+    load_timeline: InspectableButton<LoadTimelineEvent>,
+    screenshot: ResourceInspector<ScreenshotSettings>,
+    camera: ResourceInspector<CameraSettings>,
+    launch: ResourceInspector<LaunchSettings>,
+    warp: ResourceInspector<WarpSettings>,
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Self {
+            gravitational_constant: 0.0001,
+            softening: 0.1,
+            coulomb_constant: 1.0,
+            background: BackgroundField::default(),
+            background_mass: 100000.0,
+            background_acceleration: Vec3::new(0.0, -1.0, 0.0),
+            disk_scale_length: 100.0,
+            disk_scale_height: 10.0,
+            drag_coefficient: 0.47,
+            post_newtonian: false,
+            speed_of_light: 1000.0,
+            post_newtonian_range: None,
+            active: false,
+            update_frequency_ms: 34,
+            time_scale: 1.0,
+            max_steps_per_frame: 100,
+            interpolate: true,
+            simulation_step_ms: 16,
+            debug_steps: 1000,
+            debug_stride: 1,
+            prediction_horizon: None,
+            prediction_fade: 0.8,
+            live_prediction: true,
+            max_speed: None,
+            planar: false,
+            integrator: IntegratorKind::default(),
+            direction: TimeDirection::default(),
+            substeps: 1,
+            deterministic: false,
+            adaptive_timestep: false,
+            adaptive_distance: 20.0,
+            min_dt_ms: 0.5,
+            max_dt_ms: 16.0,
+            kepler_propagation: false,
+            barnes_hut: false,
+            barnes_hut_theta: 0.5,
+            gpu_forces: false,
+            zero_net_momentum: false,
+            recenter_barycenter: false,
+            show_grid: false,
+            adaptive_grid: true,
+            grid_spacing: 25.0,
+            grid_extent: 250.0,
+            collision_response: CollisionResponse::default(),
+            restitution: 1.0,
+            tidal_breakup: false,
+            fragment_count: 4,
+            auto_color: false,
+            color_low: Color::rgb(0.3, 0.5, 1.0),
+            color_mid: Color::WHITE,
+            color_high: Color::rgb(1.0, 0.85, 0.2),
+            velocity_drag_scale: 0.05,
+            scenario: Scenario::default(),
+            line_coloring: LineColoring::default(),
+            trail_length: 300,
+            trail_interval: 1,
+            rewind_history: 600,
+            rewind_seconds: 2.0,
+            boundary: Boundary::default(),
+            boundary_radius: 300.0,
+            floor_height: -50.0,
+            boundary_restitution: 0.8,
+            show_boundary: true,
+            show_potential: false,
+            potential_resolution: 64,
+            potential_update_ms: 250,
+            show_lagrange_points: false,
+            show_apsides: true,
+            show_closest_approach: true,
+            warn_impacts: true,
+            show_soi_crossings: true,
+            show_labels: true,
+            label_fade_distance: 500.0,
+            show_velocity: false,
+            show_acceleration: false,
+            vector_scale: 5.0,
+            acceleration_scale: 100.0,
+            show_field: false,
+            field_3d: false,
+            field_resolution: 15,
+            field_spacing: 10.0,
+            predict_in_parent_frame: false,
+            patched_conic_prediction: false,
+            origin_rebase_distance: 1000.0,
+            tracer_count: 1000,
+            tracer_spread: 200.0,
+        }
+    }
+}
+
+pub struct ReflectionPlugin;
+
+impl Plugin for ReflectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<Name>()
+            .register_inspectable::<Celestial>()
+            .register_inspectable::<Universe>()
+            .register_inspectable::<SimClock>()
+            .register_inspectable::<ConservationStats>();
+    }
+}
+
+/// The length of one simulation step, in simulation seconds.
+#[derive(Copy, Clone)]
+pub struct UniverseTick(pub f32);
+
+/// Drives the simulation clock. The keyboard and the inspector send these, and
+/// anything else that wants to start, stop or step the simulation should too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulationCommand {
+    Pause,
+    Resume,
+    /// Runs this many extra steps, whether or not the simulation is paused.
+    Step(u32),
+    /// Drops any requested steps still to run and ends the frame's steps.
+    /// Systems running between steps can send it to stop on the step they're
+    /// looking for.
+    Halt,
+}
+
+impl Default for SimulationCommand {
+    fn default() -> Self {
+        SimulationCommand::Step(1)
+    }
+}
+
+/// Run criteria shared by every system that has to run once per simulation
+/// step, rather than once per frame.
+#[derive(RunCriteriaLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UniverseStep;
+
+/// How well the fixed step is keeping up with real time.
+#[derive(Inspectable, Default)]
+pub struct StepBudget {
+    /// Steps run during the last frame.
+    pub last_frame_steps: u32,
+    /// Simulation time dropped because frames hit `max_steps_per_frame`.
+    pub lost_seconds: f64,
+    /// When a frame last hit the cap, in seconds since startup.
+    #[inspectable(ignore)]
+    pub last_capped: Option<f64>,
+    /// How far real time has got towards the next step, from 0 to 1. Rendering
+    /// uses it to blend between the last two steps.
+    pub step_fraction: f64,
+}
+
+#[derive(Default)]
+struct FixedStepState {
+    accumulator: f64,
+    /// Steps asked for with `SimulationCommand::Step` still to run.
+    requested: u32,
+    /// Steps run from the accumulator so far this frame.
+    taken: u32,
+    looping: bool,
+}
+
+/// Runs the simulation step once for every `update_frequency_ms` of frame
+/// time, sped up by `time_scale`, carrying the remainder over so a slow frame
+/// catches up rather than losing ticks. Catching up stops at
+/// `max_steps_per_frame`, and whatever is still owed is dropped and counted
+/// in `StepBudget`, so one slow frame can't make every following frame slower.
+/// Explicitly requested steps are always run. Time spent paused doesn't count
+/// towards the next step, and nothing runs while the menu is up. Commands are
+/// read between steps as well as at the start of the frame.
+fn universe_fixed_step(
+    time: Res<Time>,
+    mut constants: ResMut<Universe>,
+    mut commands: EventReader<SimulationCommand>,
+    mut budget: ResMut<StepBudget>,
+    app_state: Option<Res<State<AppState>>>,
+    mut state: Local<FixedStepState>,
+) -> ShouldRun {
+    if app_state.is_some_and(|app_state| *app_state.current() == AppState::Menu) {
+        return ShouldRun::No;
+    }
+    let step = constants.update_frequency_ms as f64 / 1000.0;
+    let mut halted = false;
+    for command in commands.iter() {
+        match command {
+            SimulationCommand::Pause => constants.active = false,
+            SimulationCommand::Resume => constants.active = true,
+            SimulationCommand::Step(steps) => state.requested += steps,
+            SimulationCommand::Halt => {
+                state.requested = 0;
+                halted = true;
+            }
+        }
+    }
+    if halted {
+        return finish_frame(&mut state, &mut budget, step);
+    }
+    if !state.looping {
+        if constants.active {
+            state.accumulator += time.delta_seconds_f64() * constants.time_scale as f64;
+        }
+        state.taken = 0;
+    }
+    if state.requested > 0 {
+        state.requested -= 1;
+    } else if state.accumulator >= step && state.taken >= constants.max_steps_per_frame {
+        let dropped = (state.accumulator / step).floor();
+        state.accumulator -= dropped * step;
+        budget.lost_seconds += dropped * constants.tick().0.abs() as f64;
+        budget.last_capped = Some(time.seconds_since_startup());
+        return finish_frame(&mut state, &mut budget, step);
+    } else if state.accumulator >= step {
+        state.accumulator -= step;
+        state.taken += 1;
+    } else {
+        return finish_frame(&mut state, &mut budget, step);
+    }
+    state.looping = true;
+    ShouldRun::YesAndCheckAgain
+}
+
+fn finish_frame(state: &mut FixedStepState, budget: &mut StepBudget, step: f64) -> ShouldRun {
+    budget.last_frame_steps = state.taken;
+    budget.step_fraction = (state.accumulator / step).clamp(0.0, 1.0);
+    state.looping = false;
+    ShouldRun::No
+}
+
+fn step_universe(
+    constants: Res<Universe>,
+    mut clock: ResMut<SimClock>,
+    origin: Res<FloatingOrigin>,
+    query: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    held: Query<(), With<Held>>,
+    pinned: Query<(), With<Pinned>>,
+    atmospheres: Query<&Atmosphere>,
+    mut replay: ResMut<Replay>,
+) {
+    // A replay moves the bodies itself.
+    if replay.take_replayed_step() {
+        return;
+    }
+    let tick = constants.tick();
+    update_celestial_bodies(tick, constants, origin, query, held, pinned, atmospheres);
+    clock.advance(tick.0);
+}
+
+fn update_celestial_bodies(
+    tick: UniverseTick,
+    constants: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut query: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    held: Query<(), With<Held>>,
+    pinned: Query<(), With<Pinned>>,
+    atmospheres: Query<&Atmosphere>,
+) {
+    let mut celestial_map = build_celestial_maps(&query);
+    mark_pinned(&mut celestial_map, &pinned);
+    mark_atmospheres(&mut celestial_map, &atmospheres);
+    step_celestial_map(&mut celestial_map, &tick, &constants);
+    for (this, mut body, mut transform, mut state) in query.iter_mut() {
+        if held.contains(this) {
+            continue;
+        }
+        // The map is built from this same query, so every body is in it.
+        let bundle = &celestial_map.map[&this];
+
+        state.position = bundle.pos;
+        state.velocity = bundle.vel;
+        state.acceleration = bundle.acc;
+        body.velocity = bundle.vel.as_vec3();
+        transform.translation = origin.to_render(bundle.pos);
+    }
+}
+
+fn build_celestial_maps(
+    celestial_bodies: &Query<
+        (Entity, &mut Celestial, &mut Transform, &mut SimState),
+        Without<DebugMarker>,
+    >,
+) -> CelestialMap {
+    let mut map = HashMap::new();
+
+    for (entity, body, _, state) in celestial_bodies.iter() {
+        map.insert(
+            entity,
+            CelestialBundle {
+                pos: state.position,
+                vel: state.velocity,
+                acc: state.acceleration,
+                mass: body.mass as f64,
+                charge: body.charge as f64,
+                pinned: false,
+                radius: body.radius() as f64,
+                atmosphere: None,
+            },
+        );
+    }
+
+    CelestialMap { map }
+}
+
+struct DebugManager {
+    refresh: bool,
+    active: bool,
+}
+
+impl Default for DebugManager {
+    fn default() -> Self {
+        Self {
+            refresh: false,
+            active: true,
+        }
+    }
+}
+
+fn should_update_debug_points(
+    changed: Query<
+        Entity,
+        (
+            Or<(
+                Changed<Celestial>,
+                // Not the transform, which render interpolation rewrites
+                // every frame. Moving a body reaches its state a frame later.
+                Changed<SimState>,
+                Changed<ManeuverNode>,
+                Changed<Atmosphere>,
+            )>,
+            Without<DebugMarker>,
+            With<Celestial>,
+        ),
+    >,
+    removed_nodes: RemovedComponents<ManeuverNode>,
+    removed_atmospheres: RemovedComponents<Atmosphere>,
+    key: Res<Input<KeyCode>>,
+    constants: Res<Universe>,
+    clock: Res<SimClock>,
+    mut predicted_with: Local<Option<Universe>>,
+    mut last_step_count: Local<u64>,
+    mut manager: ResMut<DebugManager>,
+) {
+    if key.just_pressed(KeyCode::Q) {
+        manager.active = true;
+    }
+    // The inspector borrows the universe mutably every frame it's drawn, so
+    // change detection can't tell an edit from a look. Compare with what the
+    // prediction was made with instead.
+    let universe_changed = predicted_with.as_ref() != Some(&*constants);
+    if universe_changed {
+        *predicted_with = Some(constants.clone());
+    }
+    // Every step moves every body, so without live updates a running
+    // simulation leaves the prediction where it was.
+    let stepped = clock.step_count != *last_step_count;
+    *last_step_count = clock.step_count;
+    let bodies_changed = !changed.is_empty() && (constants.live_prediction || !stepped);
+    if !key.just_pressed(KeyCode::Q)
+        && !universe_changed
+        && !bodies_changed
+        && removed_nodes.iter().next().is_none()
+        && removed_atmospheres.iter().next().is_none()
+    {
+        return;
+    }
+    manager.refresh = true;
+}
+
+fn generate_debug_points(
+    key: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    constants: Res<Universe>,
+    celestial_bodies: Query<
+        (Entity, &mut Celestial, &mut Transform, &mut SimState),
+        Without<DebugMarker>,
+    >,
+    mut paths: Query<(Entity, &mut PredictedPath)>,
+    names: Query<&Name>,
+    pinned: Query<(), With<Pinned>>,
+    (maneuvers, parent_bodies, atmospheres, reference_frame): (
+        Query<(Entity, &ManeuverNode)>,
+        Query<&ParentBody>,
+        Query<&Atmosphere>,
+        Res<ReferenceFrame>,
+    ),
+    clock: Res<SimClock>,
+    mut preview: ResMut<ManeuverPreview>,
+    mut manager: ResMut<DebugManager>,
+    mut export_events: EventReader<ExportTrajectoryEvent>,
+    export_settings: Res<TrajectoryExport>,
+) {
+    let export =
+        export_events.iter().count() > 0 && export_settings.source == ExportSource::Prediction;
+    if key.just_pressed(KeyCode::C) {
+        for (entity, _) in paths.iter() {
+            commands.entity(entity).remove::<PredictedPath>();
+        }
+        preview.burns.clear();
+        manager.active = false;
+        return;
+    }
+    let update_markers = manager.refresh && manager.active;
+    if !update_markers && !export {
+        return;
+    }
+    let mut celestial_map = build_celestial_maps(&celestial_bodies);
+    mark_pinned(&mut celestial_map, &pinned);
+    mark_atmospheres(&mut celestial_map, &atmospheres);
+    // Massless bodies can't change anyone else's path, so their own isn't
+    // worth predicting.
+    celestial_map.map.retain(|_, body| body.mass != 0.0);
+    let mut positions: HashMap<Entity, Vec<DVec3>> = celestial_map
+        .map
+        .iter()
+        .map(|(entity, bundle)| (*entity, vec![bundle.pos]))
+        .collect();
+    // Apsides are measured in the simulation frame, whatever the path is
+    // drawn relative to.
+    let attractors: Vec<(Entity, Entity)> = celestial_map
+        .map
+        .keys()
+        .filter_map(|entity| Some((*entity, dominant_attractor(&celestial_map, *entity)?)))
+        .collect();
+    let mut unshifted = positions.clone();
+    let mut samples = Vec::new();
+    let mut burns = Vec::new();
+    // Nodes only fire on the way forward, so a reversed prediction skips them.
+    let maneuvers: Vec<(Entity, ManeuverNode)> = maneuvers
+        .iter()
+        .filter(|_| constants.direction == TimeDirection::Forward)
+        .map(|(entity, node)| (entity, *node))
+        .collect();
+    let reference = reference_frame
+        .body
+        .filter(|body| celestial_map.map.contains_key(body));
+    let current =
+        (constants.predict_in_parent_frame || reference.is_some()).then(|| CelestialMap {
+            map: celestial_map.map.clone(),
+        });
+    let tick = constants.tick();
+    let stride = constants.debug_stride.max(1);
+    for step in 0..constants.prediction_steps() {
+        burns.extend(apply_maneuvers(
+            &mut celestial_map,
+            &maneuvers,
+            clock.step_count + step as u64,
+        ));
+        if constants.patched_conic_prediction {
+            step_patched_conics(&mut celestial_map, &tick, &constants);
+        } else {
+            step_celestial_map(&mut celestial_map, &tick, &constants);
+        }
+        if (step + 1) % stride != 0 {
+            continue;
+        }
+        for entity in celestial_map.entities(constants.deterministic) {
+            let bundle = &celestial_map.map[&entity];
+            let frame = reference.or_else(|| parent_bodies.get(entity).ok().map(|parent| parent.0));
+            let position = match (&current, frame) {
+                (Some(current), Some(frame)) => {
+                    in_parent_frame(current, &celestial_map, frame, bundle.pos)
+                }
+                _ => bundle.pos,
+            };
+            positions.entry(entity).or_default().push(position);
+            unshifted.entry(entity).or_default().push(bundle.pos);
+            if export {
+                samples.push(TrajectorySample {
+                    name: names
+                        .get(entity)
+                        .map(|name| name.name.clone())
+                        .unwrap_or_else(|_| format!("{:?}", entity)),
+                    step: clock.step_count + step as u64 + 1,
+                    time: clock.elapsed_seconds + (step + 1) as f64 * tick.0 as f64,
+                    position: bundle.pos,
+                    velocity: bundle.vel,
+                });
+            }
+        }
+    }
+
+    if export {
+        export_trajectory(&export_settings, &samples);
+    }
+    if !update_markers {
+        return;
+    }
+    manager.refresh = false;
+    preview.burns = burns;
+
+    let mut apsides: HashMap<Entity, Vec<Apsis>> = attractors
+        .into_iter()
+        .filter_map(|(entity, attractor)| {
+            let found = find_apsides(unshifted.get(&entity)?, unshifted.get(&attractor)?);
+            Some((entity, found))
+        })
+        .collect();
+    let unpathed: Vec<Entity> = positions
+        .keys()
+        .filter(|entity| !paths.contains(**entity))
+        .copied()
+        .collect();
+    let mut path_for = |entity: Entity| PredictedPath {
+        points: positions.remove(&entity).unwrap_or_default(),
+        positions: unshifted.remove(&entity).unwrap_or_default(),
+        apsides: apsides.remove(&entity).unwrap_or_default(),
+        start_seconds: clock.elapsed_seconds,
+        step_seconds: tick.0 as f64 * stride as f64,
+    };
+    for (entity, mut path) in paths.iter_mut() {
+        *path = path_for(entity);
+    }
+    for entity in unpathed {
+        commands.entity(entity).insert(path_for(entity));
+    }
+}
+
+/// Preset speeds comma and period step between.
+const TIME_SCALES: [f32; 5] = [0.1, 1.0, 10.0, 100.0, 1000.0];
+
+fn universe_toggle(
+    key: Res<Input<KeyCode>>,
+    mut universe: ResMut<Universe>,
+    mut simulation: EventWriter<SimulationCommand>,
+) {
+    if key.just_pressed(KeyCode::U) {
+        simulation.send(if universe.active {
+            SimulationCommand::Pause
+        } else {
+            SimulationCommand::Resume
+        });
+    }
+    if key.just_pressed(KeyCode::T) {
+        simulation.send(SimulationCommand::Step(1));
+    }
+    if key.just_pressed(KeyCode::I) {
+        universe.integrator = universe.integrator.next();
+        info!("switched to the {:?} integrator", universe.integrator);
+    }
+    if key.just_pressed(KeyCode::Period) {
+        if let Some(faster) = TIME_SCALES.iter().find(|s| **s > universe.time_scale) {
+            universe.time_scale = *faster;
+            info!("running at {}x", universe.time_scale);
+        }
+    }
+    if key.just_pressed(KeyCode::Comma) {
+        if let Some(slower) = TIME_SCALES.iter().rev().find(|s| **s < universe.time_scale) {
+            universe.time_scale = *slower;
+            info!("running at {}x", universe.time_scale);
+        }
+    }
+    if key.just_pressed(KeyCode::V) {
+        universe.direction = universe.direction.reversed();
+        info!("time now runs {:?}", universe.direction);
+        if universe.integrator != IntegratorKind::Leapfrog || universe.adaptive_timestep {
+            warn!("only leapfrog with a fixed step retraces its path exactly");
+        }
+    }
+    if key.just_pressed(KeyCode::M) {
+        universe.show_lagrange_points = !universe.show_lagrange_points;
+    }
+    if key.just_pressed(KeyCode::B) {
+        universe.collision_response = universe.collision_response.next();
+        info!(
+            "collision response is now {:?}",
+            universe.collision_response
+        );
+    }
+}
+
+/// Flattens every body when planar mode is switched on, and any body spawned
+/// while it is on.
+fn snap_to_plane(
+    universe: Res<Universe>,
+    mut was_planar: Local<bool>,
+    mut query: Query<(&mut Celestial, &mut Transform), Without<DebugMarker>>,
+) {
+    let switched_on = universe.planar && !*was_planar;
+    *was_planar = universe.planar;
+    if !universe.planar {
+        return;
+    }
+    for (mut body, mut transform) in query.iter_mut() {
+        if !switched_on && !body.is_added() {
+            continue;
+        }
+        if body.velocity.y != 0.0 {
+            body.velocity = universe.constrain(body.velocity);
+        }
+        if transform.translation.y != 0.0 {
+            transform.translation = universe.constrain(transform.translation);
+        }
+    }
+}
+
+pub struct UniversePlugin;
+
+impl Plugin for UniversePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Universe::default())
+            .insert_resource(DebugManager::default())
+            .init_resource::<SimClock>()
+            .init_resource::<Units>()
+            .init_resource::<Replay>()
+            .init_resource::<StepBudget>()
+            .add_event::<SimulationCommand>()
+            .add_plugin(FloatingOriginPlugin)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_radius.before(TransformSystem::TransformPropagate),
+            )
+            .add_system(universe_toggle.with_run_criteria(outside_menu))
+            .add_system(snap_to_plane.before(step_universe))
+            .add_system(
+                step_universe.with_run_criteria(RunCriteriaDescriptorCoercion::label(
+                    universe_fixed_step,
+                    UniverseStep,
+                )),
+            )
+            .add_system(should_update_debug_points);
+    }
+}
+
+pub fn run() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(InspectorPlugin::<InspectTarget>::new())
+        .add_plugin(InspectorPlugin::<UniverseInspector>::new())
+        .add_plugin(ReflectionPlugin)
+        .add_plugin(NoCameraPlayerPlugin)
+        .add_plugin(UniversePlugin)
+        .add_plugin(GpuForcesPlugin)
+        .add_plugin(ExportPlugin)
+        .add_plugin(CameraControlPlugin)
+        .add_plugin(DebugLinesPlugin::with_depth_test(true))
+        .add_plugin(OverlayPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(AppearancePlugin)
+        .add_plugin(DiagnosticsPlugin)
+        .add_plugin(DragPlugin)
+        .add_plugin(DetailsPlugin)
+        .add_plugin(ScreenshotPlugin)
+        .add_plugin(TrailPlugin)
+        .add_plugin(BoundaryPlugin)
+        .add_plugin(HeatmapPlugin)
+        .add_plugin(LagrangePlugin)
+        .add_plugin(LaunchPlugin)
+        .add_plugin(PinPlugin)
+        .add_plugin(SpinPlugin)
+        .add_plugin(TidalPlugin)
+        .add_plugin(SpacecraftPlugin)
+        .add_plugin(ManeuverPlugin)
+        .add_plugin(SphereOfInfluencePlugin)
+        .add_plugin(OrbitalElementsPlugin)
+        .add_plugin(AtmospherePlugin)
+        .add_plugin(TestParticlePlugin)
+        .add_plugin(HillSpherePlugin)
+        .add_plugin(BarycenterPlugin)
+        .add_plugin(RewindPlugin)
+        .add_plugin(RecordingPlugin)
+        .add_plugin(CheckpointPlugin)
+        .add_plugin(AppStatePlugin)
+        .add_plugin(WarpPlugin)
+        .add_plugin(TimelinePlugin)
+        .add_plugin(InterpolationPlugin)
+        .add_plugin(PredictionPlugin)
+        .add_plugin(VectorPlugin)
+        .add_plugin(FieldPlugin)
+        .add_plugin(FramePlugin)
+        .add_plugin(ApsidesPlugin)
+        .add_plugin(ClosestApproachPlugin)
+        .add_plugin(ImpactPlugin)
+        .add_plugin(SoiCrossingPlugin)
+        .add_plugin(LabelPlugin)
+        .add_plugin(MeasurePlugin)
+        .add_plugins(DefaultPickingPlugins)
+        .add_startup_system(setup)
+        .add_startup_system(setup_universe)
+        .add_system(handle_input)
+        .add_system(generate_debug_points.with_run_criteria(outside_menu))
+        .run();
+}
+
+fn handle_input(
+    mut commands: Commands,
+    query: Query<Entity, With<Celestial>>,
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<StandardMaterial>>,
+    universe: ResMut<Universe>,
+    units: ResMut<Units>,
+    mut clock: ResMut<SimClock>,
+    mut rewind: ResMut<RewindBuffer>,
+    mut load_events: EventReader<LoadScenarioEvent>,
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+) {
+    // The menu loads scenarios through events, but R only resets outside it.
+    let reset = keys.just_pressed(KeyCode::R) && *state.current() != AppState::Menu;
+    if load_events.iter().count() > 0 || reset {
+        for entity in query.iter() {
+            commands.entity(entity).despawn();
+        }
+        clock.reset();
+        rewind.clear();
+        setup_universe(commands, meshes, materials, universe, units);
+    }
+}
+
+fn setup_universe(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut universe: ResMut<Universe>,
+    mut units: ResMut<Units>,
+) {
+    let scenario = universe.scenario;
+    spawn_scenario(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut universe,
+        &mut units,
+        scenario,
+    );
+}
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 500.0, 0.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        })
+        .insert(FlyCam)
+        .insert_bundle(PickingCameraBundle::default());
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 100.0,
+    });
+}
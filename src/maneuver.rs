@@ -0,0 +1,183 @@
+use bevy::{ecs::schedule::RunCriteria, math::DVec3, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    build_celestial_maps,
+    orbits::dominant_attractor,
+    origin::{FloatingOrigin, SimState},
+    overlay::draw_arrow,
+    physics::CelestialMap,
+    pin::Pinned,
+    recording::not_replaying,
+    spacecraft::Spacecraft,
+    step_universe, Celestial, DebugMarker, InspectTarget, SimClock, Universe, UniverseStep,
+};
+
+/// A planned impulsive burn. It fires at the start of the tick that takes the
+/// clock past `step`, and the prediction already shows the path after it.
+#[derive(Component, Inspectable, Clone, Copy)]
+pub struct ManeuverNode {
+    /// Simulation step the burn happens at.
+    pub step: u64,
+    /// Delta-v along the velocity relative to the attractor.
+    #[inspectable(speed = 0.01)]
+    pub prograde: f32,
+    /// Delta-v away from the attractor, perpendicular to prograde.
+    #[inspectable(speed = 0.01)]
+    pub radial: f32,
+    /// Delta-v along the orbit normal.
+    #[inspectable(speed = 0.01)]
+    pub normal: f32,
+}
+
+/// Where the nodes fall on the current prediction, in simulation space, with
+/// the delta-v they apply there.
+#[derive(Default)]
+pub struct ManeuverPreview {
+    pub burns: Vec<(DVec3, DVec3)>,
+}
+
+/// The node's delta-v in simulation space. Directions are taken relative to
+/// the body's dominant attractor, or to the simulation frame if it has none.
+pub fn burn_delta_v(celestial_map: &CelestialMap, target: Entity, node: &ManeuverNode) -> DVec3 {
+    let body = match celestial_map.map.get(&target) {
+        Some(body) => body,
+        None => return DVec3::ZERO,
+    };
+    let (offset, velocity) = match dominant_attractor(celestial_map, target)
+        .and_then(|attractor| celestial_map.map.get(&attractor))
+    {
+        Some(attractor) => (body.pos - attractor.pos, body.vel - attractor.vel),
+        None => (DVec3::ZERO, body.vel),
+    };
+    let prograde = match velocity.try_normalize() {
+        Some(prograde) => prograde,
+        None => return DVec3::ZERO,
+    };
+    let normal = offset
+        .cross(velocity)
+        .try_normalize()
+        .unwrap_or_else(|| prograde.any_orthonormal_vector());
+    let radial = prograde.cross(normal);
+    prograde * node.prograde as f64 + radial * node.radial as f64 + normal * node.normal as f64
+}
+
+/// Applies every node due at `step` to the map, skipping pinned bodies.
+/// Returns where each one fired and the delta-v it applied.
+pub fn apply_maneuvers(
+    celestial_map: &mut CelestialMap,
+    nodes: &[(Entity, ManeuverNode)],
+    step: u64,
+) -> Vec<(DVec3, DVec3)> {
+    let mut burns = Vec::new();
+    for (entity, node) in nodes.iter().filter(|(_, node)| node.step == step) {
+        let delta_v = burn_delta_v(celestial_map, *entity, node);
+        if let Some(body) = celestial_map
+            .map
+            .get_mut(entity)
+            .filter(|body| !body.pinned)
+        {
+            body.vel += delta_v;
+            burns.push((body.pos, delta_v));
+        }
+    }
+    burns
+}
+
+/// Places a node a quarter of the way along the selected spacecraft's
+/// prediction with N, or removes the one it has.
+fn toggle_maneuver_node(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    universe: Res<Universe>,
+    clock: Res<SimClock>,
+    crafts: Query<Option<&ManeuverNode>, With<Spacecraft>>,
+) {
+    if !keys.just_pressed(KeyCode::N) {
+        return;
+    }
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    match crafts.get(target) {
+        Ok(Some(_)) => {
+            commands.entity(target).remove::<ManeuverNode>();
+        }
+        Ok(None) => {
+            commands.entity(target).insert(ManeuverNode {
+                step: clock.step_count + (universe.debug_steps / 4) as u64,
+                prograde: 0.0,
+                radial: 0.0,
+                normal: 0.0,
+            });
+        }
+        Err(_) => {}
+    }
+}
+
+/// Fires nodes once the clock reaches them, before the step that would carry
+/// the body past. Nodes that were missed, say by being placed in the past,
+/// fire straight away. A pinned craft uses up its node without moving.
+fn execute_maneuvers(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    nodes: Query<(Entity, &ManeuverNode)>,
+    pinned: Query<(), With<Pinned>>,
+    mut bodies: Query<
+        (Entity, &mut Celestial, &mut Transform, &mut SimState),
+        Without<DebugMarker>,
+    >,
+) {
+    let due: Vec<(Entity, ManeuverNode)> = nodes
+        .iter()
+        .filter(|(_, node)| clock.step_count >= node.step)
+        .map(|(entity, node)| (entity, *node))
+        .collect();
+    if due.is_empty() {
+        return;
+    }
+    let celestial_map = build_celestial_maps(&bodies);
+    for (entity, node) in due {
+        let delta_v = burn_delta_v(&celestial_map, entity, &node);
+        commands.entity(entity).remove::<ManeuverNode>();
+        if pinned.contains(entity) {
+            continue;
+        }
+        if let Ok((_, mut body, _, mut state)) = bodies.get_mut(entity) {
+            state.velocity += delta_v;
+            body.velocity = state.velocity.as_vec3();
+            info!("executed a maneuver of {:.3} delta-v", delta_v.length());
+        }
+    }
+}
+
+fn draw_maneuver_preview(
+    preview: Res<ManeuverPreview>,
+    origin: Res<FloatingOrigin>,
+    mut lines: ResMut<DebugLines>,
+) {
+    for (position, delta_v) in preview.burns.iter() {
+        let start = origin.to_render(*position);
+        let direction = delta_v.as_vec3().normalize_or_zero();
+        draw_arrow(&mut lines, start, start + direction * 10.0, Color::FUCHSIA);
+    }
+}
+
+pub struct ManeuverPlugin;
+
+impl Plugin for ManeuverPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<ManeuverNode>()
+            .init_resource::<ManeuverPreview>()
+            .add_system(toggle_maneuver_node)
+            .add_system(
+                execute_maneuvers
+                    .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                    .before(step_universe),
+            )
+            .add_system(draw_maneuver_preview);
+    }
+}
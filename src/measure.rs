@@ -0,0 +1,177 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2, FontId},
+};
+use bevy_mod_picking::{PickingCamera, Primitive3d};
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::{FloatingOrigin, SimState},
+    overlay::{draw_wire_sphere, screen_position},
+    state::outside_menu,
+    DebugMarker,
+};
+
+const MEASURE_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const MARKER_RADIUS: f32 = 0.3;
+
+/// One end of a measurement: a body, or a fixed point in simulation space.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Endpoint {
+    Body(Entity),
+    Point(DVec3),
+}
+
+pub struct Measurement {
+    pub a: Endpoint,
+    pub b: Endpoint,
+}
+
+impl Measurement {
+    fn joins(&self, a: Endpoint, b: Endpoint) -> bool {
+        (self.a == a && self.b == b) || (self.a == b && self.b == a)
+    }
+}
+
+#[derive(Default)]
+pub struct Measurements {
+    /// The first end picked of a measurement still being made.
+    pub pending: Option<Endpoint>,
+    pub lines: Vec<Measurement>,
+}
+
+/// Middle-click a body, or a point on the XZ plane, then another to measure
+/// between them. Measuring the same pair again removes it, clicking the same
+/// end twice cancels, and Shift+middle-click clears every measurement.
+fn pick_endpoints(
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    origin: Res<FloatingOrigin>,
+    mut measurements: ResMut<Measurements>,
+    picking_camera: Query<&PickingCamera, With<FlyCam>>,
+    bodies: Query<(), (With<SimState>, Without<DebugMarker>)>,
+) {
+    if !mouse.just_pressed(MouseButton::Middle) {
+        return;
+    }
+    if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        *measurements = Measurements::default();
+        return;
+    }
+    let source = match picking_camera.get_single() {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    let picked = source
+        .intersect_top()
+        .map(|(entity, _)| entity)
+        .filter(|entity| bodies.contains(*entity));
+    let endpoint = match picked {
+        Some(entity) => Endpoint::Body(entity),
+        None => match source.intersect_primitive(Primitive3d::Plane {
+            point: origin.to_render(DVec3::ZERO),
+            normal: Vec3::Y,
+        }) {
+            Some(hit) => Endpoint::Point(origin.to_simulation(hit.position())),
+            None => return,
+        },
+    };
+    let first = match measurements.pending.take() {
+        Some(first) => first,
+        None => {
+            measurements.pending = Some(endpoint);
+            return;
+        }
+    };
+    if first == endpoint {
+        return;
+    }
+    let count = measurements.lines.len();
+    measurements
+        .lines
+        .retain(|measurement| !measurement.joins(first, endpoint));
+    if measurements.lines.len() == count {
+        measurements.lines.push(Measurement {
+            a: first,
+            b: endpoint,
+        });
+    }
+}
+
+/// Each measurement is a line labeled with the live distance and relative
+/// speed between its ends. Measurements to a body that's gone are dropped.
+fn draw_measurements(
+    origin: Res<FloatingOrigin>,
+    mut measurements: ResMut<Measurements>,
+    mut lines: ResMut<DebugLines>,
+    mut egui_context: ResMut<EguiContext>,
+    camera: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    bodies: Query<&SimState>,
+) {
+    let state_of = |endpoint: Endpoint| match endpoint {
+        Endpoint::Body(entity) => bodies
+            .get(entity)
+            .ok()
+            .map(|state| (state.position, state.velocity)),
+        Endpoint::Point(point) => Some((point, DVec3::ZERO)),
+    };
+    if measurements
+        .pending
+        .is_some_and(|pending| state_of(pending).is_none())
+    {
+        measurements.pending = None;
+    }
+    measurements.lines.retain(|measurement| {
+        state_of(measurement.a).is_some() && state_of(measurement.b).is_some()
+    });
+    if let Some((position, _)) = measurements.pending.and_then(state_of) {
+        draw_wire_sphere(
+            &mut lines,
+            origin.to_render(position),
+            MARKER_RADIUS,
+            MEASURE_COLOR,
+        );
+    }
+
+    let camera = camera.get_single().ok();
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for measurement in measurements.lines.iter() {
+        let ((a, velocity_a), (b, velocity_b)) =
+            match (state_of(measurement.a), state_of(measurement.b)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+        let (start, end) = (origin.to_render(a), origin.to_render(b));
+        lines.line_colored(start, end, 0.0, MEASURE_COLOR);
+        let screen = camera.and_then(|(camera, transform)| {
+            screen_position(camera, transform, start.lerp(end, 0.5))
+        });
+        if let Some(screen) = screen {
+            painter.text(
+                screen + egui::vec2(6.0, -6.0),
+                Align2::LEFT_BOTTOM,
+                format!(
+                    "{:.2}, relative speed {:.2}",
+                    a.distance(b),
+                    velocity_a.distance(velocity_b)
+                ),
+                FontId::proportional(12.0),
+                egui::Color32::from_gray(230),
+            );
+        }
+    }
+}
+
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Measurements>()
+            .add_system(pick_endpoints.with_run_criteria(outside_menu))
+            .add_system(draw_measurements.after(pick_endpoints));
+    }
+}
@@ -0,0 +1,281 @@
+use std::f64::consts::PI;
+
+use bevy::{math::DVec3, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{
+    build_celestial_maps,
+    origin::SimState,
+    physics::{CelestialBundle, CelestialMap},
+    soi::SphereOfInfluence,
+    Celestial, DebugMarker, Universe,
+};
+
+/// Keplerian elements of a body's orbit around the body whose sphere of
+/// influence it is in. Unbound orbits have an infinite period and apoapsis.
+#[derive(Component, Inspectable, Clone, Copy, Debug, Default)]
+pub struct OrbitalElements {
+    /// Negative for hyperbolic orbits.
+    #[inspectable(read_only)]
+    pub semi_major_axis: f64,
+    #[inspectable(read_only)]
+    pub eccentricity: f64,
+    /// Degrees between the orbital plane and the XZ plane.
+    #[inspectable(read_only)]
+    pub inclination: f64,
+    #[inspectable(read_only)]
+    pub period: f64,
+    #[inspectable(read_only)]
+    pub apoapsis: f64,
+    #[inspectable(read_only)]
+    pub periapsis: f64,
+}
+
+/// Elements of `body` relative to `reference`, treating the pair as an
+/// isolated two-body system.
+pub fn orbital_elements(
+    gravitational_constant: f64,
+    body: &CelestialBundle,
+    reference: &CelestialBundle,
+) -> OrbitalElements {
+    let mu = gravitational_constant * (body.mass + reference.mass);
+    let offset = body.pos - reference.pos;
+    let velocity = body.vel - reference.vel;
+    let distance = offset.length();
+    let angular_momentum = offset.cross(velocity);
+
+    let eccentricity = (velocity.cross(angular_momentum) / mu - offset / distance).length();
+    let energy = velocity.length_squared() / 2.0 - mu / distance;
+    let semi_major_axis = -mu / (2.0 * energy);
+    let bound = eccentricity < 1.0;
+    OrbitalElements {
+        semi_major_axis,
+        eccentricity,
+        inclination: (angular_momentum.y / angular_momentum.length())
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees(),
+        period: if bound {
+            2.0 * PI * (semi_major_axis.powi(3) / mu).sqrt()
+        } else {
+            f64::INFINITY
+        },
+        apoapsis: if bound {
+            semi_major_axis * (1.0 + eccentricity)
+        } else {
+            f64::INFINITY
+        },
+        periapsis: angular_momentum.length_squared() / mu / (1.0 + eccentricity),
+    }
+}
+
+/// The heavier body pulling hardest on `target`, which is what it is taken to
+/// orbit. Returns `None` when `target` is the heaviest body or alone.
+pub fn dominant_attractor(celestial_map: &CelestialMap, target: Entity) -> Option<Entity> {
+    let this = celestial_map.map.get(&target)?;
+    celestial_map
+        .map
+        .iter()
+        .filter(|(entity, that)| **entity != target && that.mass > this.mass)
+        .map(|(entity, that)| (*entity, that.mass / this.pos.distance_squared(that.pos)))
+        .filter(|(_, pull)| pull.is_finite())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Stumpff functions C(z) and S(z) of the universal variable formulation.
+fn stumpff(z: f64) -> (f64, f64) {
+    if z > 1e-6 {
+        let root = z.sqrt();
+        ((1.0 - root.cos()) / z, (root - root.sin()) / (z * root))
+    } else if z < -1e-6 {
+        let root = (-z).sqrt();
+        ((root.cosh() - 1.0) / -z, (root.sinh() - root) / (-z * root))
+    } else {
+        (0.5, 1.0 / 6.0)
+    }
+}
+
+/// Position and velocity `dt` seconds on along the two-body orbit through
+/// `offset` and `velocity`, both relative to the attractor, for the
+/// gravitational parameter `mu`. Uses the universal anomaly, so elliptic,
+/// parabolic and hyperbolic orbits are all handled alike.
+pub fn kepler_propagate(mu: f64, offset: DVec3, velocity: DVec3, dt: f64) -> (DVec3, DVec3) {
+    let distance = offset.length();
+    let radial_speed = offset.dot(velocity) / distance;
+    let alpha = 2.0 / distance - velocity.length_squared() / mu;
+    let root_mu = mu.sqrt();
+
+    // Newton's method on the universal Kepler equation.
+    let mut chi = root_mu * alpha.abs() * dt;
+    for _ in 0..50 {
+        let z = alpha * chi * chi;
+        let (c, s) = stumpff(z);
+        let f = distance * radial_speed / root_mu * chi * chi * c
+            + (1.0 - alpha * distance) * chi.powi(3) * s
+            + distance * chi
+            - root_mu * dt;
+        let df = distance * radial_speed / root_mu * chi * (1.0 - z * s)
+            + (1.0 - alpha * distance) * chi * chi * c
+            + distance;
+        let correction = f / df;
+        chi -= correction;
+        if correction.abs() < 1e-12 {
+            break;
+        }
+    }
+
+    let z = alpha * chi * chi;
+    let (c, s) = stumpff(z);
+    let f = 1.0 - chi * chi / distance * c;
+    let g = dt - chi.powi(3) * s / root_mu;
+    let new_offset = f * offset + g * velocity;
+    let new_distance = new_offset.length();
+    let f_dot = root_mu / (new_distance * distance) * (alpha * chi.powi(3) * s - chi);
+    let g_dot = 1.0 - chi * chi / new_distance * c;
+    (new_offset, f_dot * offset + g_dot * velocity)
+}
+
+/// Radius of the Hill sphere of a body of `mass` orbiting one of
+/// `attractor_mass`, measured at periapsis where it is smallest.
+pub fn hill_radius(semi_major_axis: f64, eccentricity: f64, mass: f64, attractor_mass: f64) -> f64 {
+    semi_major_axis * (1.0 - eccentricity) * (mass / (3.0 * attractor_mass)).cbrt()
+}
+
+pub fn escape_velocity(gravitational_constant: f64, attractor_mass: f64, distance: f64) -> f64 {
+    (2.0 * gravitational_constant * attractor_mass / distance).sqrt()
+}
+
+pub fn barycenter(a: &CelestialBundle, b: &CelestialBundle) -> DVec3 {
+    (a.pos * a.mass + b.pos * b.mass) / (a.mass + b.mass)
+}
+
+/// Net force along the axis of the rotating two-body frame, with unit
+/// separation, the primary at `-mu` and the secondary at `1 - mu`.
+fn collinear_force(x: f64, mu: f64) -> f64 {
+    let to_primary = x + mu;
+    let to_secondary = x - 1.0 + mu;
+    x - (1.0 - mu) * to_primary / to_primary.abs().powi(3)
+        - mu * to_secondary / to_secondary.abs().powi(3)
+}
+
+/// Bisects `collinear_force` on an interval where it changes sign once.
+fn solve_collinear(mu: f64, mut low: f64, mut high: f64) -> f64 {
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if (collinear_force(mid, mu) < 0.0) == (collinear_force(low, mu) < 0.0) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// L1 to L5 of `secondary` orbiting `primary`, in simulation space. L4 leads the
+/// secondary along its orbit and L5 trails it.
+pub fn lagrange_points(primary: &CelestialBundle, secondary: &CelestialBundle) -> [DVec3; 5] {
+    let offset = secondary.pos - primary.pos;
+    let separation = offset.length();
+    let axis = offset / separation;
+    let normal = offset
+        .cross(secondary.vel - primary.vel)
+        .try_normalize()
+        .unwrap_or(DVec3::Y);
+    let along_orbit = normal.cross(axis);
+
+    let mu = secondary.mass / (primary.mass + secondary.mass);
+    const EPSILON: f64 = 1e-9;
+    let l1 = solve_collinear(mu, -mu + EPSILON, 1.0 - mu - EPSILON);
+    let l2 = solve_collinear(mu, 1.0 - mu + EPSILON, 2.0);
+    let l3 = solve_collinear(mu, -2.0, -mu - EPSILON);
+
+    let center = barycenter(primary, secondary);
+    let on_axis = |x: f64| center + axis * (x * separation);
+    let triangle = (0.5 - mu) * axis * separation;
+    let height = 3f64.sqrt() / 2.0 * separation * along_orbit;
+    [
+        on_axis(l1),
+        on_axis(l2),
+        on_axis(l3),
+        center + triangle + height,
+        center + triangle - height,
+    ]
+}
+
+/// Runs after spheres of influence are updated, so orbits are always
+/// measured against the body currently dominating each one.
+fn update_orbital_elements(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    changed: Query<(), Changed<SphereOfInfluence>>,
+    spheres: Query<(Entity, &SphereOfInfluence)>,
+    bodies: Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let celestial_map = build_celestial_maps(&bodies);
+    for (entity, sphere) in spheres.iter() {
+        let elements = sphere.dominant.and_then(|reference| {
+            let body = celestial_map.map.get(&entity)?;
+            let reference_body = celestial_map.map.get(&reference)?;
+            Some(orbital_elements(
+                universe.gravitational_constant as f64,
+                body,
+                reference_body,
+            ))
+        });
+        match elements {
+            Some(elements) => {
+                commands.entity(entity).insert(elements);
+            }
+            None => {
+                commands.entity(entity).remove::<OrbitalElements>();
+            }
+        }
+    }
+}
+
+pub struct OrbitalElementsPlugin;
+
+impl Plugin for OrbitalElementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<OrbitalElements>()
+            .add_system_to_stage(CoreStage::Last, update_orbital_elements);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(pos: DVec3, vel: DVec3, mass: f64) -> CelestialBundle {
+        CelestialBundle {
+            pos,
+            vel,
+            acc: DVec3::ZERO,
+            mass,
+            charge: 0.0,
+            pinned: false,
+            radius: 1.0,
+            atmosphere: None,
+        }
+    }
+
+    #[test]
+    fn triangular_points_form_equilateral_triangles() {
+        let primary = body(DVec3::new(2.0, 0.0, 1.0), DVec3::ZERO, 1.0);
+        let secondary = body(DVec3::new(2.0, 0.0, 6.0), DVec3::X, 0.01);
+        let [_, _, _, l4, l5] = lagrange_points(&primary, &secondary);
+        for point in [l4, l5] {
+            assert!((point.distance(primary.pos) - 5.0).abs() < 1e-9);
+            assert!((point.distance(secondary.pos) - 5.0).abs() < 1e-9);
+            let angle = (secondary.pos - primary.pos).angle_between(point - primary.pos);
+            assert!((angle - std::f64::consts::FRAC_PI_3).abs() < 1e-9);
+        }
+        // L4 leads the secondary along its orbit.
+        assert!((l4 - secondary.pos).dot(secondary.vel) > 0.0);
+        assert!((l5 - secondary.pos).dot(secondary.vel) < 0.0);
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+
+use crate::{Celestial, DebugMarker, Universe};
+
+/// Authoritative position and velocity of a body in simulation space. The
+/// body's `Transform` and `Celestial::velocity` are an f32 view of it,
+/// relative to the [`FloatingOrigin`].
+#[derive(Component, Clone, Copy, Default)]
+pub struct SimState {
+    pub position: DVec3,
+    pub velocity: DVec3,
+    /// Net acceleration at the start of the last step.
+    pub acceleration: DVec3,
+}
+
+/// The simulation-space point that render space is centered on. It follows
+/// the camera around, so whatever is on screen stays close to zero where f32
+/// transforms are precise.
+#[derive(Default)]
+pub struct FloatingOrigin {
+    pub origin: DVec3,
+}
+
+impl FloatingOrigin {
+    pub fn to_render(&self, position: DVec3) -> Vec3 {
+        (position - self.origin).as_vec3()
+    }
+
+    pub fn to_simulation(&self, translation: Vec3) -> DVec3 {
+        self.origin + translation.as_dvec3()
+    }
+}
+
+/// Sent with the render-space offset that was subtracted from everything when
+/// the origin moved, for anything holding on to render-space points.
+pub struct OriginShifted(pub Vec3);
+
+/// New bodies are placed in render space, so they start out relative to the
+/// current origin.
+fn attach_sim_state(
+    mut commands: Commands,
+    origin: Res<FloatingOrigin>,
+    bodies: Query<(Entity, &Celestial, &Transform), (Without<SimState>, Without<DebugMarker>)>,
+) {
+    for (entity, body, transform) in bodies.iter() {
+        commands.entity(entity).insert(SimState {
+            position: origin.to_simulation(transform.translation),
+            velocity: body.velocity.as_dvec3(),
+            acceleration: DVec3::ZERO,
+        });
+    }
+}
+
+/// Dragging, collisions, boundaries and the inspector all edit the f32 view
+/// directly. Anything that no longer matches the simulation state was changed
+/// from outside, so it's taken as the new state.
+pub fn adopt_external_edits(
+    origin: Res<FloatingOrigin>,
+    mut bodies: Query<(&Celestial, &Transform, &mut SimState)>,
+) {
+    for (body, transform, mut state) in bodies.iter_mut() {
+        if transform.translation != origin.to_render(state.position) {
+            state.position = origin.to_simulation(transform.translation);
+        }
+        if body.velocity != state.velocity.as_vec3() {
+            state.velocity = body.velocity.as_dvec3();
+        }
+    }
+}
+
+/// Moves the origin under the camera once it strays past
+/// `origin_rebase_distance`. Bodies are re-derived from their simulation
+/// state, everything else is shifted by the same offset.
+fn rebase_origin(
+    universe: Res<Universe>,
+    mut origin: ResMut<FloatingOrigin>,
+    mut shifted: EventWriter<OriginShifted>,
+    mut bodies: Query<(&SimState, &mut Transform)>,
+    mut others: Query<
+        (&mut Transform, Option<&FlyCam>),
+        (Or<(With<FlyCam>, With<DebugMarker>)>, Without<SimState>),
+    >,
+) {
+    let camera = others
+        .iter()
+        .find_map(|(transform, camera)| camera.map(|_| transform.translation));
+    // Keep the plane at render height zero in planar mode.
+    let offset = match camera {
+        Some(camera) if camera.length() > universe.origin_rebase_distance => {
+            universe.constrain(camera)
+        }
+        _ => return,
+    };
+
+    origin.origin += offset.as_dvec3();
+    for (state, mut transform) in bodies.iter_mut() {
+        transform.translation = origin.to_render(state.position);
+    }
+    for (mut transform, _) in others.iter_mut() {
+        transform.translation -= offset;
+    }
+    shifted.send(OriginShifted(offset));
+}
+
+pub struct FloatingOriginPlugin;
+
+impl Plugin for FloatingOriginPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FloatingOrigin>()
+            .add_event::<OriginShifted>()
+            .add_system_to_stage(CoreStage::PreUpdate, attach_sim_state)
+            .add_system_to_stage(CoreStage::PreUpdate, adopt_external_edits)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                rebase_origin.after(adopt_external_edits),
+            );
+    }
+}
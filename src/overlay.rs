@@ -0,0 +1,141 @@
+use std::f32::consts::TAU;
+
+use bevy::{math::DVec3, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::egui;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{origin::FloatingOrigin, Universe};
+
+const GRID_COLOR: Color = Color::rgba(0.4, 0.4, 0.4, 0.5);
+const CIRCLE_SEGMENTS: usize = 64;
+
+pub fn draw_arrow(lines: &mut DebugLines, start: Vec3, end: Vec3, color: Color) {
+    lines.line_colored(start, end, 0.0, color);
+    let shaft = end - start;
+    let direction = match shaft.try_normalize() {
+        Some(direction) => direction,
+        None => return,
+    };
+    let head_length = shaft.length() * 0.2;
+    let side = direction.any_orthonormal_vector() * head_length * 0.5;
+    let base = end - direction * head_length;
+    lines.line_colored(end, base + side, 0.0, color);
+    lines.line_colored(end, base - side, 0.0, color);
+}
+
+/// Circle of `radius` around `center` in the plane spanned by the unit
+/// vectors `u` and `v`.
+fn draw_circle(lines: &mut DebugLines, center: Vec3, u: Vec3, v: Vec3, radius: f32, color: Color) {
+    let point = |i: usize| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+        center + (u * angle.cos() + v * angle.sin()) * radius
+    };
+    for i in 0..CIRCLE_SEGMENTS {
+        lines.line_colored(point(i), point(i + 1), 0.0, color);
+    }
+}
+
+/// Three great circles outlining a sphere.
+pub fn draw_wire_sphere(lines: &mut DebugLines, center: Vec3, radius: f32, color: Color) {
+    draw_circle(lines, center, Vec3::X, Vec3::Z, radius, color);
+    draw_circle(lines, center, Vec3::X, Vec3::Y, radius, color);
+    draw_circle(lines, center, Vec3::Y, Vec3::Z, radius, color);
+}
+
+/// Where `world` lands on screen, in egui's coordinates, or `None` if it's
+/// behind the camera or outside its view.
+pub fn screen_position(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    world: Vec3,
+) -> Option<egui::Pos2> {
+    let viewport = camera.world_to_viewport(camera_transform, world)?;
+    let size = camera.logical_viewport_size()?;
+    // Viewport coordinates start at the bottom left, egui's at the top left.
+    Some(egui::pos2(viewport.x, size.y - viewport.y))
+}
+
+/// F2 shows or hides the reference grid and axes.
+fn toggle_reference_grid(keys: Res<Input<KeyCode>>, mut universe: ResMut<Universe>) {
+    if keys.just_pressed(KeyCode::F2) {
+        universe.show_grid = !universe.show_grid;
+    }
+}
+
+/// Grid spacing for a camera `height` above the grid: the power of ten
+/// below it, so roughly ten lines span the view from any distance.
+fn adaptive_spacing(height: f64, minimum: f64) -> f64 {
+    10f64.powf(height.max(minimum).log10().floor()).max(minimum)
+}
+
+fn draw_reference_grid(
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut lines: ResMut<DebugLines>,
+    camera: Query<&GlobalTransform, With<FlyCam>>,
+) {
+    if !universe.show_grid {
+        return;
+    }
+    let minimum = universe.grid_spacing.max(1.0) as f64;
+    let half_count = (universe.grid_extent as f64 / minimum).floor() as i32;
+    let camera = camera
+        .get_single()
+        .ok()
+        .map(|transform| origin.to_simulation(transform.translation()));
+    // Adapting, the grid follows the camera, snapped to its own spacing so
+    // the lines stay put as it moves.
+    let (spacing, center) = match camera {
+        Some(camera) if universe.adaptive_grid => {
+            let spacing = adaptive_spacing(camera.y.abs(), minimum);
+            let snap = |value: f64| (value / spacing).round() * spacing;
+            (spacing, DVec3::new(snap(camera.x), 0.0, snap(camera.z)))
+        }
+        _ => (minimum, DVec3::ZERO),
+    };
+    let extent = half_count as f64 * spacing;
+
+    // The grid lies in the XZ plane, which is also the plane planar mode
+    // constrains bodies to.
+    for i in -half_count..=half_count {
+        let offset = i as f64 * spacing;
+        lines.line_colored(
+            origin.to_render(center + DVec3::new(-extent, 0.0, offset)),
+            origin.to_render(center + DVec3::new(extent, 0.0, offset)),
+            0.0,
+            GRID_COLOR,
+        );
+        lines.line_colored(
+            origin.to_render(center + DVec3::new(offset, 0.0, -extent)),
+            origin.to_render(center + DVec3::new(offset, 0.0, extent)),
+            0.0,
+            GRID_COLOR,
+        );
+    }
+
+    // The axes stay on the simulation origin, however far the grid has
+    // followed the camera.
+    let axis_length = center.length() + extent;
+    for (axis, color) in [
+        (DVec3::X, Color::RED),
+        (DVec3::Y, Color::GREEN),
+        (DVec3::Z, Color::BLUE),
+    ] {
+        lines.line_colored(
+            origin.to_render(axis * -axis_length),
+            origin.to_render(axis * axis_length),
+            0.0,
+            color,
+        );
+    }
+}
+
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_reference_grid)
+            .add_system(draw_reference_grid.after(toggle_reference_grid));
+    }
+}
@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{Celestial, Radius, Universe};
+
+/// A massless tracer. It falls through the field like any body but pulls on
+/// nothing, so the force sum skips it as a source and thousands of them cost
+/// little more than their own integration. Tracers don't collide, break up,
+/// leave trails or get a prediction.
+#[derive(Component)]
+pub struct TestParticle;
+
+const TRACER_RADIUS: f32 = 0.3;
+const GOLDEN_ANGLE: f32 = PI * (3.0 - 2.236_068);
+
+/// Evenly spread offsets within `radius`, without any randomness: a sunflower
+/// disk in planar mode and a Fibonacci-spiral ball otherwise.
+fn scatter(universe: &Universe, count: u32, radius: f32) -> Vec<Vec3> {
+    (0..count)
+        .map(|i| {
+            let t = (i as f32 + 0.5) / count as f32;
+            let angle = i as f32 * GOLDEN_ANGLE;
+            if universe.planar {
+                Vec3::new(angle.cos(), 0.0, angle.sin()) * t.sqrt() * radius
+            } else {
+                let height = 1.0 - 2.0 * t;
+                let ring = (1.0 - height * height).sqrt();
+                Vec3::new(ring * angle.cos(), height, ring * angle.sin()) * t.cbrt() * radius
+            }
+        })
+        .collect()
+}
+
+/// The mesh and material every tracer shares.
+pub fn tracer_assets(
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> (Handle<Mesh>, Handle<StandardMaterial>) {
+    let mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 1.0,
+        subdivisions: 0,
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.6, 0.9, 1.0),
+        unlit: true,
+        ..default()
+    });
+    (mesh, material)
+}
+
+pub fn spawn_tracer(
+    commands: &mut Commands,
+    mesh: &Handle<Mesh>,
+    material: &Handle<StandardMaterial>,
+    position: Vec3,
+    velocity: Vec3,
+) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_translation(position).with_scale(Vec3::splat(TRACER_RADIUS)),
+            ..default()
+        })
+        .insert(Celestial::new(0.0, velocity))
+        .insert(Radius(TRACER_RADIUS))
+        .insert(TestParticle)
+        .id()
+}
+
+/// Scatters `tracer_count` test particles at rest around the render origin with
+/// Y. They share one mesh and material to keep large swarms cheap.
+fn scatter_tracers(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    universe: Res<Universe>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !keys.just_pressed(KeyCode::Y) {
+        return;
+    }
+    let (mesh, material) = tracer_assets(&mut meshes, &mut materials);
+    for offset in scatter(&universe, universe.tracer_count, universe.tracer_spread) {
+        spawn_tracer(&mut commands, &mesh, &material, offset, Vec3::ZERO);
+    }
+    info!("scattered {} tracers", universe.tracer_count);
+}
+
+pub struct TestParticlePlugin;
+
+impl Plugin for TestParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(scatter_tracers);
+    }
+}
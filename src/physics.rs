@@ -0,0 +1,1097 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use bevy::{
+    math::DVec3,
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    atmosphere::Atmosphere,
+    background::{background_acceleration, background_potential, BackgroundField},
+    barnes_hut::Octree,
+    gpu,
+    orbits::kepler_propagate,
+    soi::{influence_hierarchy, patched_conic_accelerations},
+    Universe, UniverseTick,
+};
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntegratorKind {
+    /// Semi-implicit Euler, one force evaluation per step.
+    #[default]
+    Euler,
+    /// Velocity Verlet (kick-drift-kick leapfrog). Symplectic, so energy
+    /// oscillates around its true value instead of drifting away.
+    Leapfrog,
+    /// Classic 4th-order Runge-Kutta, four force evaluations per step.
+    Rk4,
+}
+
+impl IntegratorKind {
+    pub const ALL: [IntegratorKind; 3] = [
+        IntegratorKind::Euler,
+        IntegratorKind::Leapfrog,
+        IntegratorKind::Rk4,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            IntegratorKind::Euler => IntegratorKind::Leapfrog,
+            IntegratorKind::Leapfrog => IntegratorKind::Rk4,
+            IntegratorKind::Rk4 => IntegratorKind::Euler,
+        }
+    }
+}
+
+/// Which way simulation time runs. Reversing negates every tick's dt, which
+/// retraces the path exactly only with a time-symmetric integrator such as
+/// leapfrog and a fixed step.
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeDirection {
+    #[default]
+    Forward,
+    Reverse,
+}
+
+impl TimeDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            TimeDirection::Forward => TimeDirection::Reverse,
+            TimeDirection::Reverse => TimeDirection::Forward,
+        }
+    }
+
+    pub fn sign(self) -> f32 {
+        match self {
+            TimeDirection::Forward => 1.0,
+            TimeDirection::Reverse => -1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CelestialBundle {
+    pub pos: DVec3,
+    pub vel: DVec3,
+    /// Net acceleration at the start of the last step, as the integrator's
+    /// first force evaluation found it.
+    pub acc: DVec3,
+    pub mass: f64,
+    pub charge: f64,
+    /// Pinned bodies exert forces but are never moved by a step.
+    pub pinned: bool,
+    pub radius: f64,
+    pub atmosphere: Option<Atmosphere>,
+}
+
+pub struct CelestialMap {
+    pub map: HashMap<Entity, CelestialBundle>,
+}
+
+impl CelestialMap {
+    /// The entities in the map. The hash map's own order changes from run to
+    /// run, which changes the order forces are summed in, so `deterministic`
+    /// sorts them first.
+    pub fn entities(&self, deterministic: bool) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = self.map.keys().copied().collect();
+        if deterministic {
+            entities.sort_unstable();
+        }
+        entities
+    }
+}
+
+/// A pairwise interaction that contributes to each body's acceleration.
+/// Every active law is summed when the forces on a body are evaluated.
+pub trait ForceLaw: Sync {
+    /// Acceleration `this` feels because of `that`.
+    fn acceleration(&self, this: &CelestialBundle, that: &CelestialBundle) -> DVec3;
+}
+
+pub struct Gravity {
+    pub gravitational_constant: f64,
+    pub softening: f64,
+}
+
+impl ForceLaw for Gravity {
+    fn acceleration(&self, this: &CelestialBundle, that: &CelestialBundle) -> DVec3 {
+        calculate_dt_velocity(
+            self.gravitational_constant,
+            self.softening,
+            this.pos,
+            that.pos,
+            that.mass,
+        )
+    }
+}
+
+/// Electrostatic force `k * q1 * q2 / r²`, repelling like charges and
+/// attracting opposite ones. Softened like gravity, so coincident charges
+/// don't blow up.
+pub struct Coulomb {
+    pub coulomb_constant: f64,
+    pub softening: f64,
+}
+
+impl ForceLaw for Coulomb {
+    fn acceleration(&self, this: &CelestialBundle, that: &CelestialBundle) -> DVec3 {
+        if this.mass <= 0.0 {
+            return DVec3::ZERO;
+        }
+        let offset = this.pos - that.pos;
+        let square_distance = offset.length_squared() + self.softening * self.softening;
+        if square_distance == 0.0 {
+            return DVec3::ZERO;
+        }
+        let force = self.coulomb_constant * this.charge * that.charge;
+        offset * force / (square_distance * square_distance.sqrt() * this.mass)
+    }
+}
+
+/// Drag on `this` while it is inside the atmosphere of `that`:
+/// `a = -½ * ρ * C_d * A * |v| * v / m`, for the velocity `v` relative to
+/// `that`, the cross section `A` of `this` and the density `ρ` at its altitude.
+pub struct AtmosphericDrag {
+    pub drag_coefficient: f64,
+}
+
+/// Beyond this many scale heights the atmosphere is treated as vacuum.
+const ATMOSPHERE_CUTOFF: f64 = 20.0;
+
+impl ForceLaw for AtmosphericDrag {
+    fn acceleration(&self, this: &CelestialBundle, that: &CelestialBundle) -> DVec3 {
+        let atmosphere = match that.atmosphere {
+            Some(atmosphere) => atmosphere,
+            None => return DVec3::ZERO,
+        };
+        let altitude = this.pos.distance(that.pos) - that.radius;
+        if this.mass <= 0.0 || altitude > ATMOSPHERE_CUTOFF * atmosphere.scale_height as f64 {
+            return DVec3::ZERO;
+        }
+        let velocity = this.vel - that.vel;
+        let area = std::f64::consts::PI * this.radius * this.radius;
+        -0.5 * atmosphere.density_at(altitude) * self.drag_coefficient * area / this.mass
+            * velocity.length()
+            * velocity
+    }
+}
+
+/// First-order post-Newtonian correction to gravity, added on top of
+/// [`Gravity`]. For `r` pointing from `that` to `this` and relative velocity `v`:
+///
+/// `a = G * M / (c² * r³) * ((4 * G * M / r - v²) * r + 4 * (r · v) * v)`
+///
+/// It makes tight orbits precess by about `6π * G * M / (c² * a * (1 - e²))`
+/// per revolution and vanishes as `c` grows.
+pub struct PostNewtonian {
+    pub gravitational_constant: f64,
+    pub speed_of_light: f64,
+}
+
+impl ForceLaw for PostNewtonian {
+    fn acceleration(&self, this: &CelestialBundle, that: &CelestialBundle) -> DVec3 {
+        let r = this.pos - that.pos;
+        let v = this.vel - that.vel;
+        let distance = r.length();
+        let gm = self.gravitational_constant * that.mass;
+        let scale = gm / (self.speed_of_light.powi(2) * distance.powi(3));
+        scale * ((4.0 * gm / distance - v.length_squared()) * r + 4.0 * r.dot(v) * v)
+    }
+}
+
+/// Advances every body in the map by a single tick. Both the live simulation
+/// and the debug prediction go through here so they can't drift apart.
+pub fn step_celestial_map(
+    celestial_map: &mut CelestialMap,
+    tick: &UniverseTick,
+    constants: &Universe,
+) {
+    if constants.kepler_propagation {
+        step_kepler(celestial_map, tick, constants);
+    } else {
+        step_nbody(celestial_map, tick, constants);
+    }
+}
+
+fn step_nbody(celestial_map: &mut CelestialMap, tick: &UniverseTick, constants: &Universe) {
+    step_with(celestial_map, tick, constants, |_| {
+        Box::new(|bodies| accelerations_at(constants, bodies))
+    });
+}
+
+/// Moves bodies that have a heavier body's sphere of influence to themselves
+/// along their Kepler orbit around it, and integrates the rest as usual.
+/// Those bodies stop pulling on anything, which is the approximation the
+/// sphere of influence already makes, and they cost almost nothing per tick.
+fn step_kepler(celestial_map: &mut CelestialMap, tick: &UniverseTick, constants: &Universe) {
+    let entities = celestial_map.entities(constants.deterministic);
+    let bodies: Vec<CelestialBundle> = entities.iter().map(|e| celestial_map.map[e]).collect();
+    let hierarchy = influence_hierarchy(&bodies);
+    let mut has_children = vec![false; bodies.len()];
+    for influence in &hierarchy {
+        if let Some(parent) = influence.parent {
+            has_children[parent] = true;
+        }
+    }
+
+    let gravitational_constant = constants.gravitational_constant as f64;
+    let satellites: Vec<(Entity, Entity, f64, DVec3, DVec3)> = hierarchy
+        .iter()
+        .enumerate()
+        .filter(|(this, _)| !has_children[*this] && !bodies[*this].pinned)
+        .filter_map(|(this, influence)| {
+            let parent = influence.parent?;
+            let mu = gravitational_constant * (bodies[this].mass + bodies[parent].mass);
+            let offset = bodies[this].pos - bodies[parent].pos;
+            (mu > 0.0 && offset != DVec3::ZERO).then(|| {
+                (
+                    entities[this],
+                    entities[parent],
+                    mu,
+                    offset,
+                    bodies[this].vel - bodies[parent].vel,
+                )
+            })
+        })
+        .collect();
+
+    let mut numeric = CelestialMap {
+        map: celestial_map
+            .map
+            .iter()
+            .filter(|(entity, _)| {
+                !satellites
+                    .iter()
+                    .any(|(satellite, ..)| satellite == *entity)
+            })
+            .map(|(entity, body)| (*entity, *body))
+            .collect(),
+    };
+    step_nbody(&mut numeric, tick, constants);
+    celestial_map.map.extend(numeric.map);
+
+    for (satellite, parent, mu, offset, velocity) in satellites {
+        let parent = celestial_map.map[&parent];
+        let acc = -mu * offset / offset.length().powi(3);
+        let (offset, velocity) = kepler_propagate(mu, offset, velocity, tick.0 as f64);
+        let body = celestial_map.map.get_mut(&satellite).unwrap();
+        body.acc = acc;
+        body.pos = constants.constrain_f64(parent.pos + offset);
+        body.vel = constants.constrain_f64(parent.vel + velocity);
+    }
+}
+
+/// Like [`step_celestial_map`], but every body only feels the body whose
+/// sphere of influence it is in, as in a patched-conic approximation. The
+/// hierarchy is worked out once per tick, after which each force evaluation
+/// is linear in the number of bodies.
+pub fn step_patched_conics(
+    celestial_map: &mut CelestialMap,
+    tick: &UniverseTick,
+    constants: &Universe,
+) {
+    step_with(celestial_map, tick, constants, |bodies| {
+        let hierarchy = influence_hierarchy(bodies);
+        Box::new(move |bodies| patched_conic_accelerations(constants, &hierarchy, bodies))
+    });
+}
+
+type Accelerations<'a> = Box<dyn Fn(&[CelestialBundle]) -> Vec<DVec3> + 'a>;
+
+/// Advances the map by one tick, with `accelerations` preparing the force
+/// evaluation from the state at the start of the tick.
+fn step_with<'a>(
+    celestial_map: &mut CelestialMap,
+    tick: &UniverseTick,
+    constants: &'a Universe,
+    accelerations: impl FnOnce(&[CelestialBundle]) -> Accelerations<'a>,
+) {
+    let entities = celestial_map.entities(constants.deterministic);
+    let mut bodies: Vec<CelestialBundle> = entities.iter().map(|e| celestial_map.map[e]).collect();
+
+    let substeps = substep_count(constants, tick.0 as f64, &bodies);
+    let dt = tick.0 as f64 / substeps as f64;
+    let prepared = accelerations(&bodies);
+    // Every integrator starts by evaluating the forces on the state it was
+    // given, so the first evaluation is the acceleration at the start.
+    let initial: RefCell<Option<Vec<DVec3>>> = RefCell::new(None);
+    let accelerations: Accelerations = Box::new(|bodies| {
+        let result = prepared(bodies);
+        initial.borrow_mut().get_or_insert_with(|| result.clone());
+        result
+    });
+    for _ in 0..substeps {
+        let stepped = match constants.integrator {
+            IntegratorKind::Euler => step_euler(constants, dt, &bodies, &accelerations),
+            IntegratorKind::Leapfrog => step_leapfrog(constants, dt, &bodies, &accelerations),
+            IntegratorKind::Rk4 => step_rk4(constants, dt, &bodies, &accelerations),
+        };
+        for (body, stepped) in bodies.iter_mut().zip(stepped) {
+            if !body.pinned {
+                *body = stepped;
+            }
+        }
+    }
+    if constants.zero_net_momentum {
+        remove_net_momentum(&mut bodies);
+    }
+    if constants.recenter_barycenter {
+        recenter_on_barycenter(&mut bodies);
+    }
+
+    let initial = initial.take().unwrap_or_default();
+    for (i, (entity, body)) in entities.iter().zip(bodies).enumerate() {
+        let bundle = celestial_map.map.get_mut(entity).unwrap();
+        bundle.acc = initial.get(i).copied().unwrap_or_default();
+        if bundle.pinned {
+            continue;
+        }
+        bundle.vel = constants.constrain_f64(body.vel);
+        bundle.pos = constants.constrain_f64(body.pos);
+    }
+}
+
+/// How many substeps to split a tick of `dt` seconds into. At least
+/// `substeps`; with adaptive timestepping the step also shrinks in proportion
+/// to the closest approach once it falls below `adaptive_distance`, staying
+/// within the configured bounds. Reversed ticks are split the same way.
+fn substep_count(constants: &Universe, dt: f64, bodies: &[CelestialBundle]) -> u32 {
+    let fixed = constants.substeps.max(1);
+    let dt = dt.abs();
+    if !constants.adaptive_timestep || dt == 0.0 {
+        return fixed;
+    }
+    let mut closest = f64::MAX;
+    for (i, this) in bodies.iter().enumerate() {
+        for that in &bodies[i + 1..] {
+            closest = closest.min(this.pos.distance(that.pos));
+        }
+    }
+    let min_dt = constants.min_dt_ms.max(0.001) as f64 / 1000.0;
+    let max_dt = (constants.max_dt_ms as f64 / 1000.0).max(min_dt);
+    let target = (dt * closest / constants.adaptive_distance as f64).clamp(min_dt, max_dt);
+    ((dt / target).ceil() as u32).max(fixed)
+}
+
+/// Shifts every velocity by the mass-weighted mean so the total linear
+/// momentum is zero, undoing the drift accumulated from numerical error. A
+/// pinned body can't take up momentum, so nothing is shifted while there is
+/// one.
+fn remove_net_momentum(bodies: &mut [CelestialBundle]) {
+    let total_mass: f64 = bodies.iter().map(|body| body.mass).sum();
+    if total_mass <= 0.0 || bodies.iter().any(|body| body.pinned) {
+        return;
+    }
+    let momentum = bodies.iter().fold(DVec3::ZERO, |momentum, body| {
+        momentum + body.vel * body.mass
+    });
+    let drift = momentum / total_mass;
+    for body in bodies {
+        body.vel -= drift;
+    }
+}
+
+/// Moves the whole system so its barycenter sits at the origin and is at rest,
+/// so accumulated drift can't carry it off screen. A pinned body already
+/// anchors the frame, so nothing is moved while there is one.
+fn recenter_on_barycenter(bodies: &mut [CelestialBundle]) {
+    let total_mass: f64 = bodies.iter().map(|body| body.mass).sum();
+    if total_mass <= 0.0 || bodies.iter().any(|body| body.pinned) {
+        return;
+    }
+    let (weighted_position, momentum) =
+        bodies
+            .iter()
+            .fold((DVec3::ZERO, DVec3::ZERO), |(position, momentum), body| {
+                (
+                    position + body.pos * body.mass,
+                    momentum + body.vel * body.mass,
+                )
+            });
+    let (barycenter, drift) = (weighted_position / total_mass, momentum / total_mass);
+    for body in bodies {
+        body.pos -= barycenter;
+        body.vel -= drift;
+    }
+}
+
+fn limit_speed(constants: &Universe, velocity: DVec3) -> DVec3 {
+    match constants.max_speed {
+        Some(max_speed) => velocity.clamp_length_max(max_speed as f64),
+        None => velocity,
+    }
+}
+
+/// Copies of `bodies` moved to the given positions and velocities.
+fn with_state(
+    bodies: &[CelestialBundle],
+    positions: &[DVec3],
+    velocities: &[DVec3],
+) -> Vec<CelestialBundle> {
+    bodies
+        .iter()
+        .zip(positions.iter().zip(velocities))
+        .map(|(body, (pos, vel))| CelestialBundle {
+            pos: *pos,
+            vel: *vel,
+            ..*body
+        })
+        .collect()
+}
+
+fn step_euler(
+    constants: &Universe,
+    dt: f64,
+    bodies: &[CelestialBundle],
+    accelerations: &Accelerations,
+) -> Vec<CelestialBundle> {
+    let accelerations = accelerations(bodies);
+    bodies
+        .iter()
+        .zip(&accelerations)
+        .map(|(body, a)| {
+            let vel = limit_speed(constants, body.vel + *a * dt);
+            CelestialBundle {
+                pos: body.pos + vel * dt,
+                vel,
+                ..*body
+            }
+        })
+        .collect()
+}
+
+fn step_leapfrog(
+    constants: &Universe,
+    dt: f64,
+    bodies: &[CelestialBundle],
+    accelerations: &Accelerations,
+) -> Vec<CelestialBundle> {
+    let kick = |bodies: &[CelestialBundle], accelerations: &[DVec3]| -> Vec<CelestialBundle> {
+        bodies
+            .iter()
+            .zip(accelerations)
+            .map(|(body, a)| CelestialBundle {
+                vel: body.vel + *a * (dt / 2.0),
+                ..*body
+            })
+            .collect()
+    };
+
+    let half_kicked = kick(bodies, &accelerations(bodies));
+    let drifted: Vec<CelestialBundle> = half_kicked
+        .iter()
+        .map(|body| CelestialBundle {
+            pos: body.pos + body.vel * dt,
+            ..*body
+        })
+        .collect();
+    kick(&drifted, &accelerations(&drifted))
+        .into_iter()
+        .map(|body| CelestialBundle {
+            vel: limit_speed(constants, body.vel),
+            ..body
+        })
+        .collect()
+}
+
+fn step_rk4(
+    constants: &Universe,
+    dt: f64,
+    bodies: &[CelestialBundle],
+    accelerations: &Accelerations,
+) -> Vec<CelestialBundle> {
+    let offset = |base: &[DVec3], delta: &[DVec3], scale: f64| -> Vec<DVec3> {
+        base.iter()
+            .zip(delta)
+            .map(|(b, d)| *b + *d * scale)
+            .collect()
+    };
+    let positions: Vec<DVec3> = bodies.iter().map(|body| body.pos).collect();
+    let velocities: Vec<DVec3> = bodies.iter().map(|body| body.vel).collect();
+
+    // Each stage's velocity derivative is sampled at that stage's velocity too,
+    // so velocity-dependent force laws see a consistent state.
+    let k1_x = velocities.clone();
+    let k1_v = accelerations(bodies);
+    let k2_x = offset(&velocities, &k1_v, dt / 2.0);
+    let k2_v = accelerations(&with_state(
+        bodies,
+        &offset(&positions, &k1_x, dt / 2.0),
+        &k2_x,
+    ));
+    let k3_x = offset(&velocities, &k2_v, dt / 2.0);
+    let k3_v = accelerations(&with_state(
+        bodies,
+        &offset(&positions, &k2_x, dt / 2.0),
+        &k3_x,
+    ));
+    let k4_x = offset(&velocities, &k3_v, dt);
+    let k4_v = accelerations(&with_state(bodies, &offset(&positions, &k3_x, dt), &k4_x));
+
+    let combine = |base: &[DVec3], k1: &[DVec3], k2: &[DVec3], k3: &[DVec3], k4: &[DVec3]| {
+        (0..base.len())
+            .map(|i| base[i] + (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]) * (dt / 6.0))
+            .collect::<Vec<DVec3>>()
+    };
+    let new_positions = combine(&positions, &k1_x, &k2_x, &k3_x, &k4_x);
+    let new_velocities: Vec<DVec3> = combine(&velocities, &k1_v, &k2_v, &k3_v, &k4_v)
+        .into_iter()
+        .map(|v| limit_speed(constants, v))
+        .collect();
+    with_state(bodies, &new_positions, &new_velocities)
+}
+
+/// Below this many bodies, spreading the force sum over threads costs more
+/// than it saves.
+const PARALLEL_THRESHOLD: usize = 64;
+
+/// Net acceleration on each body for a hypothetical state of the system, so
+/// multi-stage integrators can sample the field mid-step.
+pub fn accelerations_at(constants: &Universe, bodies: &[CelestialBundle]) -> Vec<DVec3> {
+    let gravity = Gravity {
+        gravitational_constant: constants.gravitational_constant as f64,
+        softening: constants.softening as f64,
+    };
+    let coulomb = Coulomb {
+        coulomb_constant: constants.coulomb_constant as f64,
+        softening: constants.softening as f64,
+    };
+    let drag = AtmosphericDrag {
+        drag_coefficient: constants.drag_coefficient as f64,
+    };
+    let post_newtonian = PostNewtonian {
+        gravitational_constant: constants.gravitational_constant as f64,
+        speed_of_light: constants.speed_of_light as f64,
+    };
+    let mut laws: Vec<&dyn ForceLaw> = Vec::new();
+    // Barnes-Hut only approximates gravity; every other law is summed pairwise.
+    let octree = constants.barnes_hut.then(|| Octree::new(bodies));
+    let gpu_gravity = if octree.is_none() && constants.gpu_forces {
+        gpu::gravity_accelerations(&gravity, bodies)
+    } else {
+        None
+    };
+    if octree.is_none() && gpu_gravity.is_none() {
+        laws.push(&gravity);
+    }
+    // With a range set, the correction only comes from the heaviest body and
+    // only reaches bodies close to it, like Mercury around the Sun.
+    let post_newtonian_source = match constants.post_newtonian_range {
+        Some(range) if constants.post_newtonian => bodies
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.mass.total_cmp(&b.mass))
+            .map(|(heaviest, _)| (heaviest, range as f64)),
+        _ => None,
+    };
+    if constants.post_newtonian && post_newtonian_source.is_none() {
+        laws.push(&post_newtonian);
+    }
+    if bodies.iter().any(|body| body.charge != 0.0) {
+        laws.push(&coulomb);
+    }
+    if bodies.iter().any(|body| body.atmosphere.is_some()) {
+        laws.push(&drag);
+    }
+
+    let acceleration_of = |this: usize| {
+        let this_body = &bodies[this];
+        let mut acceleration = match &octree {
+            Some(octree) => constants.constrain_f64(octree.acceleration(
+                &gravity,
+                constants.barnes_hut_theta as f64,
+                bodies,
+                this,
+            )),
+            None => gpu_gravity.as_ref().map_or(DVec3::ZERO, |gpu_gravity| {
+                constants.constrain_f64(gpu_gravity[this])
+            }),
+        };
+        if constants.background != BackgroundField::None {
+            acceleration +=
+                constants.constrain_f64(background_acceleration(constants, this_body.pos));
+        }
+        if let Some((heaviest, range)) = post_newtonian_source {
+            let source = &bodies[heaviest];
+            if this != heaviest && this_body.pos.distance(source.pos) <= range {
+                acceleration +=
+                    constants.constrain_f64(post_newtonian.acceleration(this_body, source));
+            }
+        }
+        if laws.is_empty() {
+            return acceleration;
+        }
+        for (that, that_body) in bodies.iter().enumerate() {
+            // Massless test particles are only ever on the receiving end.
+            if this == that || that_body.mass == 0.0 {
+                continue;
+            }
+
+            for law in &laws {
+                acceleration += constants.constrain_f64(law.acceleration(this_body, that_body));
+            }
+        }
+        acceleration
+    };
+
+    if bodies.len() < PARALLEL_THRESHOLD {
+        return (0..bodies.len()).map(acceleration_of).collect();
+    }
+    // Each body's sum runs in the same order whichever thread picks it up, and
+    // the scope returns chunks in spawn order, so results are deterministic.
+    let pool = ComputeTaskPool::init(TaskPool::default);
+    let chunk_size = (bodies.len() / pool.thread_num().max(1)).max(1);
+    let acceleration_of = &acceleration_of;
+    pool.scope(|scope| {
+        for start in (0..bodies.len()).step_by(chunk_size) {
+            let end = (start + chunk_size).min(bodies.len());
+            scope.spawn(async move { (start..end).map(acceleration_of).collect::<Vec<DVec3>>() });
+        }
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Acceleration `this` feels from `that`: magnitude `G * M / r²`, pointing
+/// from `this` toward `that`. It only depends on the attracting mass, so
+/// swapping the pair flips the direction and scales by the ratio of masses.
+///
+/// `softening` replaces `r²` with `r² + ε²`, which bounds the pull during
+/// close encounters and makes it fall to zero as the bodies coincide.
+pub fn calculate_dt_velocity(
+    gravitational_constant: f64,
+    softening: f64,
+    this_translation: DVec3,
+    that_translation: DVec3,
+    that_mass: f64,
+) -> DVec3 {
+    let offset = that_translation - this_translation;
+    let square_distance = offset.length_squared() + softening * softening;
+    if square_distance == 0.0 {
+        return DVec3::ZERO;
+    }
+    offset * gravitational_constant * that_mass / (square_distance * square_distance.sqrt())
+}
+
+/// Softened distance, consistent with the force in [`calculate_dt_velocity`].
+fn softened_distance(constants: &Universe, this: DVec3, that: DVec3) -> f64 {
+    let softening = constants.softening as f64;
+    (this.distance_squared(that) + softening * softening).sqrt()
+}
+
+/// Total kinetic and potential energy of the bodies in the map, counting
+/// gravity, the electrostatic interaction between charges and the background
+/// field.
+pub fn system_energy(constants: &Universe, celestial_map: &CelestialMap) -> (f64, f64) {
+    let bodies: Vec<&CelestialBundle> = celestial_map
+        .entities(constants.deterministic)
+        .iter()
+        .map(|entity| &celestial_map.map[entity])
+        .collect();
+    let kinetic = bodies
+        .iter()
+        .map(|body| 0.5 * body.mass * body.vel.length_squared())
+        .sum();
+    let mut potential: f64 = bodies
+        .iter()
+        .map(|body| body.mass * background_potential(constants, body.pos))
+        .sum();
+    for (i, this) in bodies.iter().enumerate() {
+        for that in &bodies[i + 1..] {
+            let distance = softened_distance(constants, this.pos, that.pos);
+            potential -= constants.gravitational_constant as f64 * this.mass * that.mass / distance;
+            potential += constants.coulomb_constant as f64 * this.charge * that.charge / distance;
+        }
+    }
+    (kinetic, potential)
+}
+
+/// Total linear momentum `Σ m * v` and angular momentum `Σ m * r × v` about
+/// the origin of the bodies in the map.
+pub fn system_momentum(constants: &Universe, celestial_map: &CelestialMap) -> (DVec3, DVec3) {
+    celestial_map
+        .entities(constants.deterministic)
+        .iter()
+        .map(|entity| &celestial_map.map[entity])
+        .fold((DVec3::ZERO, DVec3::ZERO), |(linear, angular), body| {
+            let momentum = body.vel * body.mass;
+            (linear + momentum, angular + body.pos.cross(momentum))
+        })
+}
+
+/// Acceleration a massless, uncharged test particle would feel at `point`:
+/// gravity from every body, summed directly with [`calculate_dt_velocity`],
+/// plus the background field.
+pub fn field_at(constants: &Universe, celestial_map: &CelestialMap, point: DVec3) -> DVec3 {
+    let gravity: DVec3 = celestial_map
+        .map
+        .values()
+        .filter(|body| body.mass != 0.0)
+        .map(|body| {
+            calculate_dt_velocity(
+                constants.gravitational_constant as f64,
+                constants.softening as f64,
+                point,
+                body.pos,
+                body.mass,
+            )
+        })
+        .fold(DVec3::ZERO, |sum, acceleration| sum + acceleration);
+    let background = if constants.background != BackgroundField::None {
+        background_acceleration(constants, point)
+    } else {
+        DVec3::ZERO
+    };
+    constants.constrain_f64(gravity + background)
+}
+
+/// Gravitational potential `-Σ G * m / r` at `point`, plus the background
+/// field's. Distances are clamped to `min_distance` so sampling right on top
+/// of a body stays finite.
+pub fn potential_at(
+    constants: &Universe,
+    celestial_map: &CelestialMap,
+    point: DVec3,
+    min_distance: f64,
+) -> f64 {
+    background_potential(constants, point)
+        + celestial_map
+            .map
+            .values()
+            .map(|body| {
+                -constants.gravitational_constant as f64 * body.mass
+                    / softened_distance(constants, point, body.pos).max(min_distance)
+            })
+            .sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(pos: DVec3, vel: DVec3, mass: f64) -> CelestialBundle {
+        CelestialBundle {
+            pos,
+            vel,
+            acc: DVec3::ZERO,
+            mass,
+            charge: 0.0,
+            pinned: false,
+            radius: 1.0,
+            atmosphere: None,
+        }
+    }
+
+    fn celestial_map(bodies: &[CelestialBundle]) -> CelestialMap {
+        CelestialMap {
+            map: bodies
+                .iter()
+                .enumerate()
+                .map(|(i, body)| (Entity::from_raw(i as u32), *body))
+                .collect(),
+        }
+    }
+
+    fn get(celestial_map: &CelestialMap, i: u32) -> CelestialBundle {
+        celestial_map.map[&Entity::from_raw(i)]
+    }
+
+    /// Steps `ticks` times, calling `each` after every step.
+    fn run(
+        celestial_map: &mut CelestialMap,
+        constants: &Universe,
+        dt: f32,
+        ticks: usize,
+        mut each: impl FnMut(&CelestialMap),
+    ) {
+        for _ in 0..ticks {
+            step_celestial_map(celestial_map, &UniverseTick(dt), constants);
+            each(celestial_map);
+        }
+    }
+
+    #[test]
+    fn gravity_follows_inverse_square() {
+        let acceleration = calculate_dt_velocity(2.0, 0.0, DVec3::ZERO, DVec3::X * 3.0, 5.0);
+        assert!((acceleration.length() - 2.0 * 5.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gravity_points_at_the_other_body() {
+        let this = DVec3::new(1.0, 2.0, 3.0);
+        let that = DVec3::new(-4.0, 0.5, 7.0);
+        let acceleration = calculate_dt_velocity(1.0, 0.0, this, that, 10.0);
+        let toward = (that - this).normalize();
+        assert!(acceleration.normalize().distance(toward) < 1e-12);
+    }
+
+    #[test]
+    fn gravity_scales_with_the_other_mass() {
+        let (light, heavy) = (2.0, 8.0);
+        let on_light = calculate_dt_velocity(1.0, 0.0, DVec3::ZERO, DVec3::Z, heavy);
+        let on_heavy = calculate_dt_velocity(1.0, 0.0, DVec3::Z, DVec3::ZERO, light);
+        assert!((on_light + on_heavy * heavy / light).length() < 1e-12);
+    }
+
+    #[test]
+    fn softened_gravity_is_finite_at_zero_distance() {
+        let acceleration = calculate_dt_velocity(1.0, 0.1, DVec3::ONE, DVec3::ONE, 1e6);
+        assert!(acceleration.is_finite());
+        assert!(calculate_dt_velocity(1.0, 0.0, DVec3::ONE, DVec3::ONE, 1e6).is_finite());
+    }
+
+    #[test]
+    fn max_speed_caps_near_collisions() {
+        let near_miss = [
+            body(DVec3::new(-0.005, 0.0, 0.0), DVec3::ZERO, 1e6),
+            body(DVec3::new(0.005, 0.0, 0.0), DVec3::Z, 1e6),
+        ];
+        let top_speed = |max_speed| {
+            let constants = Universe {
+                gravitational_constant: 1.0,
+                softening: 0.0,
+                max_speed,
+                ..default()
+            };
+            let mut bodies = celestial_map(&near_miss);
+            let mut top_speed: f64 = 0.0;
+            run(&mut bodies, &constants, 0.01, 5, |bodies| {
+                for body in bodies.map.values() {
+                    top_speed = top_speed.max(body.vel.length());
+                }
+            });
+            top_speed
+        };
+        assert!(top_speed(Some(100.0)) <= 100.0 + 1e-9);
+        assert!(top_speed(None) > 1000.0);
+    }
+
+    /// A light body on a circular orbit of radius 1 around a unit mass.
+    fn circular_orbit() -> CelestialMap {
+        celestial_map(&[
+            body(DVec3::ZERO, DVec3::ZERO, 1.0),
+            body(DVec3::X, DVec3::Z, 1e-3),
+        ])
+    }
+
+    fn gravity_only(integrator: IntegratorKind) -> Universe {
+        Universe {
+            gravitational_constant: 1.0,
+            softening: 0.0,
+            integrator,
+            ..default()
+        }
+    }
+
+    /// The largest relative energy error over 1000 ticks, and where the light
+    /// body ended up.
+    fn energy_drift(integrator: IntegratorKind) -> (f64, DVec3) {
+        let constants = gravity_only(integrator);
+        let mut bodies = circular_orbit();
+        let total = |bodies: &CelestialMap| {
+            let (kinetic, potential) = system_energy(&constants, bodies);
+            kinetic + potential
+        };
+        let initial = total(&bodies);
+        let mut drift: f64 = 0.0;
+        run(&mut bodies, &constants, 0.01, 1000, |bodies| {
+            drift = drift.max(((total(bodies) - initial) / initial).abs());
+        });
+        (drift, get(&bodies, 1).pos)
+    }
+
+    #[test]
+    fn rk4_conserves_energy_better_than_euler() {
+        let (euler, _) = energy_drift(IntegratorKind::Euler);
+        let (rk4, rk4_end) = energy_drift(IntegratorKind::Rk4);
+        let (leapfrog, leapfrog_end) = energy_drift(IntegratorKind::Leapfrog);
+        assert!(rk4 < euler / 100.0, "rk4 {} euler {}", rk4, euler);
+        assert!(leapfrog < euler, "leapfrog {} euler {}", leapfrog, euler);
+        assert!(rk4_end.distance(leapfrog_end) < 1e-2);
+    }
+
+    /// How far the periapsis of an orbit with e = 0.3 turns over three
+    /// revolutions.
+    fn periapsis_shift(periapsis: f64, post_newtonian: bool) -> f64 {
+        let constants = Universe {
+            post_newtonian,
+            speed_of_light: 10.0,
+            ..gravity_only(IntegratorKind::Rk4)
+        };
+        let eccentricity = 0.3;
+        let speed = ((1.0 + eccentricity) / periapsis).sqrt();
+        let mut bodies = celestial_map(&[
+            CelestialBundle {
+                pinned: true,
+                ..body(DVec3::ZERO, DVec3::ZERO, 1.0)
+            },
+            body(DVec3::X * periapsis, DVec3::Z * speed, 1e-6),
+        ]);
+        let eccentricity_vector = |bodies: &CelestialMap| {
+            let satellite = get(bodies, 1);
+            let (r, v) = (satellite.pos, satellite.vel);
+            (v.length_squared() - 1.0 / r.length()) * r - r.dot(v) * v
+        };
+        let initial = eccentricity_vector(&bodies);
+        let semi_major_axis = periapsis / (1.0 - eccentricity);
+        let period = 2.0 * std::f64::consts::PI * semi_major_axis.powf(1.5);
+        let ticks = 6000;
+        let dt = (3.0 * period / ticks as f64) as f32;
+        run(&mut bodies, &constants, dt, ticks, |_| {});
+        initial.angle_between(eccentricity_vector(&bodies))
+    }
+
+    #[test]
+    fn tight_orbits_precess() {
+        // 6π * G * M / (c² * a * (1 - e²)) is about 0.145 per revolution.
+        let tight = periapsis_shift(1.0, true);
+        assert!((0.3..0.6).contains(&tight), "turned {}", tight);
+        let wide = periapsis_shift(100.0, true);
+        assert!(wide < 0.02, "turned {}", wide);
+        let newtonian = periapsis_shift(1.0, false);
+        assert!(newtonian < 0.01, "turned {}", newtonian);
+    }
+
+    #[test]
+    fn zero_net_momentum_removes_drift() {
+        let drifting = [
+            body(DVec3::ZERO, DVec3::X, 3.0),
+            body(DVec3::Z * 10.0, DVec3::Y, 1.0),
+        ];
+        let momentum_after = |zero_net_momentum| {
+            let constants = Universe {
+                zero_net_momentum,
+                ..gravity_only(IntegratorKind::Leapfrog)
+            };
+            let mut bodies = celestial_map(&drifting);
+            run(&mut bodies, &constants, 0.01, 10, |_| {});
+            system_momentum(&constants, &bodies).0
+        };
+        assert!(momentum_after(true).length() < 1e-9);
+        assert!(momentum_after(false).distance(DVec3::new(3.0, 1.0, 0.0)) < 1e-9);
+    }
+
+    #[test]
+    fn zero_net_momentum_leaves_orbits_around_pinned_bodies_alone() {
+        let constants = Universe {
+            zero_net_momentum: true,
+            ..gravity_only(IntegratorKind::Leapfrog)
+        };
+        let mut bodies = celestial_map(&[
+            CelestialBundle {
+                pinned: true,
+                ..body(DVec3::ZERO, DVec3::ZERO, 1.0)
+            },
+            body(DVec3::X, DVec3::Z, 1e-3),
+        ]);
+        run(&mut bodies, &constants, 0.01, 1000, |bodies| {
+            let satellite = get(bodies, 1);
+            assert!(
+                (satellite.pos.length() - 1.0).abs() < 1e-3,
+                "{}",
+                satellite.pos
+            );
+            assert!(
+                (satellite.vel.length() - 1.0).abs() < 1e-3,
+                "{}",
+                satellite.vel
+            );
+        });
+    }
+
+    #[test]
+    fn pinned_bodies_stay_put() {
+        let mut bodies = celestial_map(&[
+            CelestialBundle {
+                pinned: true,
+                ..body(DVec3::ONE, DVec3::ZERO, 1.0)
+            },
+            body(DVec3::ONE + DVec3::X, DVec3::Z, 0.5),
+        ]);
+        let mut last = DVec3::X;
+        let mut angle = 0.0;
+        run(
+            &mut bodies,
+            &gravity_only(IntegratorKind::Leapfrog),
+            0.01,
+            1000,
+            |bodies| {
+                let anchor = get(bodies, 0);
+                assert_eq!(anchor.pos, DVec3::ONE);
+                assert_eq!(anchor.vel, DVec3::ZERO);
+                let offset = get(bodies, 1).pos - anchor.pos;
+                assert!((0.9..1.1).contains(&offset.length()), "{}", offset);
+                angle += last.angle_between(offset);
+                last = offset;
+            },
+        );
+        // Ten time units is most of two orbits.
+        assert!(angle > 3.0 * std::f64::consts::PI, "only turned {}", angle);
+    }
+
+    fn charged(pos: DVec3, vel: DVec3, mass: f64, charge: f64) -> CelestialBundle {
+        CelestialBundle {
+            charge,
+            ..body(pos, vel, mass)
+        }
+    }
+
+    fn electrostatic() -> Universe {
+        Universe {
+            gravitational_constant: 0.0,
+            softening: 0.0,
+            coulomb_constant: 1.0,
+            integrator: IntegratorKind::Leapfrog,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn coincident_charges_stay_finite() {
+        let a = charged(DVec3::ZERO, DVec3::ZERO, 1.0, 1.0);
+        for softening in [0.0, 0.1] {
+            let coulomb = Coulomb {
+                coulomb_constant: 1.0,
+                softening,
+            };
+            assert!(coulomb.acceleration(&a, &a).is_finite());
+        }
+    }
+
+    #[test]
+    fn like_charges_fly_apart() {
+        let mut bodies = celestial_map(&[
+            charged(DVec3::new(-0.5, 0.0, 0.0), DVec3::ZERO, 1.0, 1.0),
+            charged(DVec3::new(0.5, 0.0, 0.0), DVec3::ZERO, 1.0, 1.0),
+        ]);
+        let mut last = 1.0;
+        run(&mut bodies, &electrostatic(), 0.01, 200, |bodies| {
+            let distance = get(bodies, 0).pos.distance(get(bodies, 1).pos);
+            assert!(distance > last);
+            last = distance;
+        });
+        assert!(last > 2.0, "only got {} apart", last);
+    }
+
+    #[test]
+    fn opposite_charges_orbit() {
+        // A light charge circling a heavy one, with k * q1 * q2 / r = v² at r = 1.
+        let mut bodies = celestial_map(&[
+            charged(DVec3::ZERO, DVec3::ZERO, 1e9, 1.0),
+            charged(DVec3::X, DVec3::Z, 1.0, -1.0),
+        ]);
+        let mut angle: f64 = 0.0;
+        let mut last = DVec3::X;
+        // Two periods of 2π.
+        run(&mut bodies, &electrostatic(), 0.001, 12_600, |bodies| {
+            let offset = get(bodies, 1).pos - get(bodies, 0).pos;
+            assert!((0.95..1.05).contains(&offset.length()), "{}", offset);
+            angle += last.angle_between(offset);
+            last = offset;
+        });
+        assert!(angle > 3.9 * std::f64::consts::PI, "only turned {}", angle);
+    }
+}
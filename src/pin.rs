@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+use crate::{physics::CelestialMap, state::outside_menu, Celestial, InspectTarget};
+
+/// Holds a body in place. It still pulls on everything else, but the
+/// simulation never moves it, and collisions treat it as immovable.
+#[derive(Component)]
+pub struct Pinned;
+
+/// Flags the pinned bodies of a map built from the current world.
+pub fn mark_pinned(celestial_map: &mut CelestialMap, pinned: &Query<(), With<Pinned>>) {
+    for (entity, bundle) in celestial_map.map.iter_mut() {
+        bundle.pinned = pinned.contains(*entity);
+    }
+}
+
+/// Pins or releases the selected body with P.
+fn toggle_pinned(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    bodies: Query<Option<&Pinned>, With<Celestial>>,
+) {
+    if !keys.just_pressed(KeyCode::P) {
+        return;
+    }
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    match bodies.get(target) {
+        Ok(Some(_)) => {
+            commands.entity(target).remove::<Pinned>();
+        }
+        Ok(None) => {
+            commands.entity(target).insert(Pinned);
+        }
+        Err(_) => {}
+    }
+}
+
+pub struct PinPlugin;
+
+impl Plugin for PinPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_pinned.with_run_criteria(outside_menu));
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_mod_picking::Highlighting;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    appearance::{base_material, segment_colors},
+    apsides::Apsis,
+    origin::FloatingOrigin,
+    Universe,
+};
+
+/// Where a body is predicted to go, starting from where it is now. Kept in
+/// simulation space like `Trail`, and redrawn every frame as one line strip.
+#[derive(Component, Default)]
+pub struct PredictedPath {
+    /// The path as drawn, which may be relative to a reference body.
+    pub points: Vec<DVec3>,
+    /// The same steps in the simulation frame, for comparing bodies.
+    pub positions: Vec<DVec3>,
+    /// Closest and farthest points from the body it orbits.
+    pub apsides: Vec<Apsis>,
+    /// Simulation time of the first point.
+    pub start_seconds: f64,
+    /// Simulation time between points.
+    pub step_seconds: f64,
+}
+
+impl PredictedPath {
+    /// Simulation time at point `index`.
+    pub fn time_at(&self, index: usize) -> f64 {
+        self.start_seconds + index as f64 * self.step_seconds
+    }
+}
+
+/// Paths are colored by `line_coloring` and fade by `prediction_fade` towards
+/// the clear color along their length.
+fn draw_predictions(
+    universe: Res<Universe>,
+    clear_color: Res<ClearColor>,
+    origin: Res<FloatingOrigin>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut lines: ResMut<DebugLines>,
+    paths: Query<(
+        &PredictedPath,
+        &Handle<StandardMaterial>,
+        Option<&Highlighting<StandardMaterial>>,
+    )>,
+) {
+    let background = Vec4::from(clear_color.0.as_rgba_f32());
+    for (path, material, highlighting) in paths.iter() {
+        let color = match materials.get(base_material(material, highlighting)) {
+            Some(material) => material.base_color,
+            None => continue,
+        };
+        // Speeds are measured in the simulation frame, not the drawn one.
+        let colors = segment_colors(&universe, color, &path.positions, true);
+        let count = path.points.len().max(2) - 1;
+        for (i, ((start, end), color)) in path
+            .points
+            .iter()
+            .zip(path.points.iter().skip(1))
+            .zip(colors)
+            .enumerate()
+        {
+            let t = i as f32 / count as f32 * universe.prediction_fade;
+            let color = Vec4::from(color.as_rgba_f32());
+            let [r, g, b, a] = color.lerp(background, t).to_array();
+            lines.line_colored(
+                origin.to_render(*start),
+                origin.to_render(*end),
+                0.0,
+                Color::rgba(r, g, b, a),
+            );
+        }
+    }
+}
+
+pub struct PredictionPlugin;
+
+impl Plugin for PredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_predictions);
+    }
+}
@@ -0,0 +1,479 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+use bevy::{ecs::schedule::ShouldRun, math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    origin::{FloatingOrigin, SimState},
+    scenario::{spawn_body, BodySpec},
+    step_universe, Celestial, DebugMarker, Name, Radius, SimClock, Universe, UniverseStep,
+};
+
+#[derive(Inspectable)]
+pub struct RecordingSettings {
+    pub path: String,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            path: "recording.csv".to_string(),
+        }
+    }
+}
+
+/// Starts writing every step to `RecordingSettings::path`, or stops and
+/// flushes the file. F6 sends it too.
+#[derive(Default)]
+pub struct ToggleRecordingEvent;
+
+/// Starts playing back `RecordingSettings::path`, or stops. F7 sends it too.
+#[derive(Default)]
+pub struct ToggleReplayEvent;
+
+/// Everything needed to spawn a recorded body again.
+struct RecordedBody {
+    name: String,
+    mass: f32,
+    radius: f32,
+    color: Color,
+}
+
+struct RecordedState {
+    id: u64,
+    position: DVec3,
+    velocity: DVec3,
+    mass: f32,
+}
+
+struct RecordedStep {
+    step_count: u64,
+    elapsed_seconds: f64,
+    states: Vec<RecordedState>,
+}
+
+/// Writes one line per event. A `body` line introduces a body the first time
+/// it's seen, then each step is a `step` line followed by a `state` line per
+/// body:
+///
+/// ```text
+/// body,id,mass,radius,r,g,b,name
+/// step,step_count,elapsed_seconds
+/// state,id,x,y,z,vx,vy,vz,mass
+/// ```
+///
+/// Names go last so they can hold commas.
+#[derive(Default)]
+pub struct Recorder {
+    writer: Option<BufWriter<File>>,
+    written: HashSet<Entity>,
+}
+
+/// A recording being played back. Steps are applied one per simulation step
+/// in place of the integrator, so pausing and the time scale work as usual.
+#[derive(Default)]
+pub struct Replay {
+    bodies: HashMap<u64, RecordedBody>,
+    steps: Vec<RecordedStep>,
+    next: usize,
+    spawned: HashMap<u64, Entity>,
+    /// Set when a recorded step was applied, until the integrator sees it.
+    replayed_step: bool,
+}
+
+impl Replay {
+    pub fn is_playing(&self) -> bool {
+        self.next < self.steps.len()
+    }
+
+    /// Whether this simulation step was taken from the recording, including
+    /// the last one. Clears the flag for the next step.
+    pub fn take_replayed_step(&mut self) -> bool {
+        std::mem::take(&mut self.replayed_step)
+    }
+}
+
+/// Piped after `UniverseStep` for systems that move, spawn or despawn bodies
+/// on each step. A replay already has the outcome of all of that on file, so
+/// they sit it out.
+pub fn not_replaying(In(step): In<ShouldRun>, replay: Res<Replay>) -> ShouldRun {
+    match step {
+        ShouldRun::Yes if replay.is_playing() => ShouldRun::No,
+        ShouldRun::YesAndCheckAgain if replay.is_playing() => ShouldRun::NoAndCheckAgain,
+        step => step,
+    }
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed recording line: {}", line),
+    )
+}
+
+fn parse_fields<T: std::str::FromStr>(fields: &[&str], line: &str) -> io::Result<Vec<T>> {
+    fields
+        .iter()
+        .map(|field| field.parse().map_err(|_| invalid_line(line)))
+        .collect()
+}
+
+fn read_recording(path: &str) -> io::Result<Replay> {
+    let mut replay = Replay::default();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.splitn(8, ',').collect();
+        match fields[0] {
+            "body" if fields.len() == 8 => {
+                let id = fields[1].parse().map_err(|_| invalid_line(&line))?;
+                let values: Vec<f32> = parse_fields(&fields[2..7], &line)?;
+                replay.bodies.insert(
+                    id,
+                    RecordedBody {
+                        name: fields[7].to_string(),
+                        mass: values[0],
+                        radius: values[1],
+                        color: Color::rgb(values[2], values[3], values[4]),
+                    },
+                );
+            }
+            "step" if fields.len() == 3 => replay.steps.push(RecordedStep {
+                step_count: fields[1].parse().map_err(|_| invalid_line(&line))?,
+                elapsed_seconds: fields[2].parse().map_err(|_| invalid_line(&line))?,
+                states: Vec::new(),
+            }),
+            "state" => {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() != 9 {
+                    return Err(invalid_line(&line));
+                }
+                let values: Vec<f64> = parse_fields(&fields[2..9], &line)?;
+                let state = RecordedState {
+                    id: fields[1].parse().map_err(|_| invalid_line(&line))?,
+                    position: DVec3::new(values[0], values[1], values[2]),
+                    velocity: DVec3::new(values[3], values[4], values[5]),
+                    mass: values[6] as f32,
+                };
+                match replay.steps.last_mut() {
+                    Some(step) => step.states.push(state),
+                    None => return Err(invalid_line(&line)),
+                }
+            }
+            _ => return Err(invalid_line(&line)),
+        }
+    }
+    Ok(replay)
+}
+
+fn toggle_recording(
+    keys: Res<Input<KeyCode>>,
+    mut events: EventReader<ToggleRecordingEvent>,
+    settings: Res<RecordingSettings>,
+    mut recorder: ResMut<Recorder>,
+) {
+    if events.iter().count() == 0 && !keys.just_pressed(KeyCode::F6) {
+        return;
+    }
+    if let Some(mut writer) = recorder.writer.take() {
+        match writer.flush() {
+            Ok(()) => info!("stopped recording to {}", settings.path),
+            Err(err) => error!("failed to finish recording {}: {}", settings.path, err),
+        }
+        return;
+    }
+    match File::create(&settings.path) {
+        Ok(file) => {
+            recorder.writer = Some(BufWriter::new(file));
+            recorder.written.clear();
+            info!("recording to {}", settings.path);
+        }
+        Err(err) => error!("failed to start recording {}: {}", settings.path, err),
+    }
+}
+
+fn write_step(
+    writer: &mut BufWriter<File>,
+    written: &mut HashSet<Entity>,
+    clock: &SimClock,
+    materials: &Assets<StandardMaterial>,
+    bodies: &Query<
+        (
+            Entity,
+            &Celestial,
+            &SimState,
+            &Radius,
+            Option<&Name>,
+            Option<&Handle<StandardMaterial>>,
+        ),
+        Without<DebugMarker>,
+    >,
+) -> io::Result<()> {
+    for (entity, body, _, radius, name, material) in bodies.iter() {
+        if !written.insert(entity) {
+            continue;
+        }
+        let color = material
+            .and_then(|handle| materials.get(handle))
+            .map_or(Color::WHITE, |material| material.base_color);
+        let name = name.map_or(String::new(), |name| name.name.replace('\n', " "));
+        writeln!(
+            writer,
+            "body,{},{},{},{},{},{},{}",
+            entity.to_bits(),
+            body.mass,
+            radius.0,
+            color.r(),
+            color.g(),
+            color.b(),
+            name
+        )?;
+    }
+    writeln!(
+        writer,
+        "step,{},{}",
+        clock.step_count, clock.elapsed_seconds
+    )?;
+    for (entity, body, state, ..) in bodies.iter() {
+        let (p, v) = (state.position, state.velocity);
+        writeln!(
+            writer,
+            "state,{},{},{},{},{},{},{},{}",
+            entity.to_bits(),
+            p.x,
+            p.y,
+            p.z,
+            v.x,
+            v.y,
+            v.z,
+            body.mass
+        )?;
+    }
+    Ok(())
+}
+
+fn record_step(
+    clock: Res<SimClock>,
+    settings: Res<RecordingSettings>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut recorder: ResMut<Recorder>,
+    bodies: Query<
+        (
+            Entity,
+            &Celestial,
+            &SimState,
+            &Radius,
+            Option<&Name>,
+            Option<&Handle<StandardMaterial>>,
+        ),
+        Without<DebugMarker>,
+    >,
+) {
+    let recorder = &mut *recorder;
+    let writer = match recorder.writer.as_mut() {
+        Some(writer) => writer,
+        None => return,
+    };
+    if let Err(err) = write_step(writer, &mut recorder.written, &clock, &materials, &bodies) {
+        error!("stopped recording to {}: {}", settings.path, err);
+        recorder.writer = None;
+    }
+}
+
+/// Starting a replay clears out the current bodies; the recorded ones are
+/// spawned as they first appear. Stopping leaves them where the replay got
+/// to, ready to simulate on from there.
+fn toggle_replay(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut events: EventReader<ToggleReplayEvent>,
+    settings: Res<RecordingSettings>,
+    mut replay: ResMut<Replay>,
+    bodies: Query<Entity, With<Celestial>>,
+) {
+    if events.iter().count() == 0 && !keys.just_pressed(KeyCode::F7) {
+        return;
+    }
+    if replay.is_playing() {
+        *replay = Replay::default();
+        info!("stopped replaying {}", settings.path);
+        return;
+    }
+    match read_recording(&settings.path) {
+        Ok(recording) => {
+            for entity in bodies.iter() {
+                commands.entity(entity).despawn();
+            }
+            info!(
+                "replaying {} steps from {}",
+                recording.steps.len(),
+                settings.path
+            );
+            *replay = recording;
+        }
+        Err(err) => error!("failed to read recording {}: {}", settings.path, err),
+    }
+}
+
+fn replay_step(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut clock: ResMut<SimClock>,
+    mut replay: ResMut<Replay>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bodies: Query<(&mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+) {
+    if !replay.is_playing() {
+        return;
+    }
+    let replay = &mut *replay;
+    let step = &replay.steps[replay.next];
+    replay.next += 1;
+    replay.replayed_step = true;
+    let mut present = HashSet::new();
+    for state in step.states.iter() {
+        present.insert(state.id);
+        let entity = match replay.spawned.get(&state.id) {
+            Some(entity) => *entity,
+            None => {
+                let recorded = match replay.bodies.get(&state.id) {
+                    Some(recorded) => recorded,
+                    None => continue,
+                };
+                let entity = spawn_body(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &universe,
+                    BodySpec {
+                        name: recorded.name.clone(),
+                        mass: recorded.mass,
+                        radius: recorded.radius,
+                        color: recorded.color,
+                        position: origin.to_render(state.position),
+                        velocity: state.velocity.as_vec3(),
+                        parent: None,
+                    },
+                );
+                replay.spawned.insert(state.id, entity);
+                continue;
+            }
+        };
+        if let Ok((mut body, mut transform, mut sim_state)) = bodies.get_mut(entity) {
+            sim_state.position = state.position;
+            sim_state.velocity = state.velocity;
+            body.velocity = state.velocity.as_vec3();
+            body.mass = state.mass;
+            transform.translation = origin.to_render(state.position);
+        }
+    }
+    // Bodies that merged or broke apart in the recording go away here too.
+    replay.spawned.retain(|id, entity| {
+        let keep = present.contains(id);
+        if !keep {
+            commands.entity(*entity).despawn();
+        }
+        keep
+    });
+    clock.step_count = step.step_count;
+    clock.elapsed_seconds = step.elapsed_seconds;
+    if !replay.is_playing() {
+        info!("replay finished");
+    }
+}
+
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RecordingSettings>()
+            .init_resource::<Recorder>()
+            .add_event::<ToggleRecordingEvent>()
+            .add_event::<ToggleReplayEvent>()
+            .add_system(toggle_recording)
+            .add_system(toggle_replay)
+            .add_system(
+                replay_step
+                    .with_run_criteria(UniverseStep)
+                    .after(toggle_replay)
+                    .before(step_universe),
+            )
+            .add_system(
+                record_step
+                    .with_run_criteria(UniverseStep)
+                    .after(step_universe)
+                    .after(toggle_recording),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, input::InputPlugin};
+
+    use super::*;
+    use crate::{
+        collision::{CollisionPlugin, CollisionResponse},
+        SimulationCommand, UniversePlugin,
+    };
+
+    /// Two bodies that run into each other on the second step and are still
+    /// overlapping on the third, as if the recording had collisions off.
+    const RECORDING: &str = "\
+body,1,1,1,1,0,0,A
+body,2,1,1,0,1,0,B
+step,1,0.016
+state,1,0,0,0,0,0,0,1
+state,2,10,0,0,-1,0,0,1
+step,2,0.032
+state,1,0,0,0,0,0,0,1
+state,2,0.5,0,0,-1,0,0,1
+step,3,0.048
+state,1,0,0,0,0,0,0,1
+state,2,0.25,0,0,-1,0,0,1
+";
+
+    #[test]
+    fn replays_collisions_as_recorded() {
+        let path = std::env::temp_dir().join("replays_collisions_as_recorded.csv");
+        std::fs::write(&path, RECORDING).unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(UniversePlugin)
+            .add_plugin(CollisionPlugin)
+            .add_plugin(RecordingPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>();
+        app.world.resource_mut::<Universe>().collision_response = CollisionResponse::Merge;
+        app.world.resource_mut::<RecordingSettings>().path = path.to_string_lossy().into();
+        app.world.send_event(ToggleReplayEvent);
+        app.update();
+
+        let recorded = read_recording(&path.to_string_lossy()).unwrap();
+        for step in recorded.steps.iter() {
+            app.world.send_event(SimulationCommand::Step(1));
+            app.update();
+            // Bodies pick up their simulation state the frame after they
+            // spawn, so compare where they are drawn.
+            let mut bodies = app.world.query::<(&Name, &Transform)>();
+            let replayed: Vec<_> = bodies.iter(&app.world).collect();
+            assert_eq!(replayed.len(), step.states.len());
+            for state in step.states.iter() {
+                let name = &recorded.bodies[&state.id].name;
+                let (_, replayed) = replayed
+                    .iter()
+                    .find(|(replayed, _)| &replayed.name == name)
+                    .unwrap();
+                assert_eq!(replayed.translation, state.position.as_vec3(), "{}", name);
+            }
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use bevy::{math::DVec3, prelude::*};
+
+use crate::{
+    origin::{FloatingOrigin, SimState},
+    step_universe,
+    trails::Trail,
+    Celestial, DebugMarker, SimClock, Universe, UniverseStep,
+};
+
+#[derive(Clone, Copy)]
+struct BodySnapshot {
+    entity: Entity,
+    position: DVec3,
+    velocity: DVec3,
+    mass: f32,
+}
+
+struct Snapshot {
+    clock: SimClock,
+    bodies: Vec<BodySnapshot>,
+}
+
+/// The last `rewind_history` steps of the simulation, newest at the back.
+#[derive(Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl RewindBuffer {
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Every body's position and velocity at every recorded step, oldest
+    /// first.
+    pub fn history(&self) -> impl Iterator<Item = (&SimClock, Entity, DVec3, DVec3)> + '_ {
+        self.snapshots.iter().flat_map(|snapshot| {
+            snapshot
+                .bodies
+                .iter()
+                .map(|body| (&snapshot.clock, body.entity, body.position, body.velocity))
+        })
+    }
+}
+
+fn record_snapshot(
+    universe: Res<Universe>,
+    clock: Res<SimClock>,
+    mut buffer: ResMut<RewindBuffer>,
+    bodies: Query<(Entity, &Celestial, &SimState), Without<DebugMarker>>,
+) {
+    buffer.snapshots.push_back(Snapshot {
+        clock: clock.clone(),
+        bodies: bodies
+            .iter()
+            .map(|(entity, body, state)| BodySnapshot {
+                entity,
+                position: state.position,
+                velocity: state.velocity,
+                mass: body.mass,
+            })
+            .collect(),
+    });
+    while buffer.snapshots.len() > universe.rewind_history {
+        buffer.snapshots.pop_front();
+    }
+}
+
+/// Backspace winds the simulation back `rewind_seconds` of simulation time, or
+/// as far as the buffer goes. Bodies that have been destroyed since stay gone,
+/// and ones created since are left where they are. Restored bodies lose their
+/// trails, which would otherwise run on past where they now are.
+fn rewind(
+    keys: Res<Input<KeyCode>>,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut clock: ResMut<SimClock>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut bodies: Query<
+        (
+            &mut Celestial,
+            &mut Transform,
+            &mut SimState,
+            Option<&mut Trail>,
+        ),
+        Without<DebugMarker>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::Back) {
+        return;
+    }
+    let now = clock.elapsed_seconds;
+    // Keep the snapshot rewound to, so rewinding again carries on from it.
+    while buffer.snapshots.len() > 1
+        && (now - buffer.snapshots.back().unwrap().clock.elapsed_seconds).abs()
+            < universe.rewind_seconds as f64
+    {
+        buffer.snapshots.pop_back();
+    }
+    let snapshot = match buffer.snapshots.back() {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+    for saved in snapshot.bodies.iter() {
+        if let Ok((mut body, mut transform, mut state, trail)) = bodies.get_mut(saved.entity) {
+            state.position = saved.position;
+            state.velocity = saved.velocity;
+            body.velocity = saved.velocity.as_vec3();
+            body.mass = saved.mass;
+            transform.translation = origin.to_render(saved.position);
+            if let Some(mut trail) = trail {
+                trail.clear();
+            }
+        }
+    }
+    *clock = snapshot.clock.clone();
+    info!(
+        "rewound {:.2} seconds to step {}",
+        (now - clock.elapsed_seconds).abs(),
+        clock.step_count
+    );
+}
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindBuffer>()
+            .add_system(
+                record_snapshot
+                    .with_run_criteria(UniverseStep)
+                    .after(step_universe),
+            )
+            .add_system(rewind.before(step_universe));
+    }
+}
@@ -0,0 +1,407 @@
+use bevy::{math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+use bevy_mod_picking::PickableBundle;
+
+use crate::{
+    frames::ParentBody,
+    spin::Spin,
+    units::{Units, GRAVITATIONAL_CONSTANT_SI},
+    Celestial, Name, Radius, Universe,
+};
+
+#[derive(Inspectable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scenario {
+    /// A heavy body with a planet and a small moon.
+    #[default]
+    ThreeBody,
+    /// The Sun with Mercury, Venus, Earth and Mars on circular orbits.
+    InnerSolarSystem,
+    /// Three equal masses chasing each other along a figure-eight.
+    FigureEight,
+    /// Two equal stars orbiting each other with a circumbinary planet.
+    BinaryWithPlanet,
+    /// A star with a planet and a moon, the moon set up relative to the planet.
+    EarthMoon,
+    /// The Sun and inner planets from real masses and distances, in
+    /// `Units::SOLAR_SYSTEM`.
+    RealInnerPlanets,
+}
+
+#[derive(Default)]
+pub struct LoadScenarioEvent;
+
+pub struct BodySpec {
+    pub name: String,
+    pub mass: f32,
+    /// Together with `mass` this sets the body's density.
+    pub radius: f32,
+    pub color: Color,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Index of an earlier body of the same scenario. When set, `position`
+    /// and `velocity` are relative to that body.
+    pub parent: Option<usize>,
+}
+
+/// Velocity for a circular orbit at `offset` from a body of `central_mass`,
+/// perpendicular to the offset within the XZ plane.
+pub fn circular_orbit_velocity(
+    gravitational_constant: f32,
+    central_mass: f32,
+    offset: Vec3,
+) -> Vec3 {
+    let speed = (gravitational_constant * central_mass / offset.length()).sqrt();
+    Vec3::Y.cross(offset).normalize_or_zero() * speed
+}
+
+/// Offset `radius` from the center in the XZ plane, `phase` radians round from
+/// the X axis.
+pub fn on_circle(radius: f32, phase: f32) -> Vec3 {
+    Vec3::new(phase.cos(), 0.0, phase.sin()) * radius
+}
+
+// The figure-eight solution (Chenciner & Montgomery) is given for G = m = 1.
+// Scaling lengths by L and time by T keeps it valid when G * m = L³ / T².
+const FIGURE_EIGHT_LENGTH: f32 = 100.0;
+const FIGURE_EIGHT_TIME: f32 = 3.162_278;
+const FIGURE_EIGHT_MASS: f32 = 1000.0;
+
+const SUN_MASS_SI: f64 = 1.989e30;
+// Real radii would be specks at the distances involved.
+const SUN_RADIUS_SCALE: f64 = 10.0;
+const PLANET_RADIUS_SCALE: f64 = 300.0;
+
+impl Scenario {
+    pub const ALL: [Scenario; 6] = [
+        Scenario::ThreeBody,
+        Scenario::InnerSolarSystem,
+        Scenario::FigureEight,
+        Scenario::BinaryWithPlanet,
+        Scenario::EarthMoon,
+        Scenario::RealInnerPlanets,
+    ];
+
+    /// The units the scenario's numbers are in.
+    pub fn units(&self) -> Units {
+        match self {
+            Scenario::RealInnerPlanets => Units::SOLAR_SYSTEM,
+            _ => Units::default(),
+        }
+    }
+
+    pub fn gravitational_constant(&self) -> f32 {
+        match self {
+            Scenario::ThreeBody => 0.0001,
+            Scenario::InnerSolarSystem => 1.0,
+            Scenario::FigureEight => {
+                FIGURE_EIGHT_LENGTH.powi(3) / (FIGURE_EIGHT_TIME.powi(2) * FIGURE_EIGHT_MASS)
+            }
+            Scenario::BinaryWithPlanet => 10.0,
+            Scenario::EarthMoon => 1.0,
+            Scenario::RealInnerPlanets => self.units().gravitational_constant() as f32,
+        }
+    }
+
+    fn bodies(&self) -> Vec<BodySpec> {
+        let g = self.gravitational_constant();
+        match self {
+            Scenario::ThreeBody => vec![
+                BodySpec {
+                    name: "Left".to_string(),
+                    mass: 1000000.0,
+                    radius: 12.0,
+                    color: Color::RED,
+                    position: Vec3::ZERO,
+                    velocity: Vec3::ZERO,
+                    parent: None,
+                },
+                BodySpec {
+                    name: "Right".to_string(),
+                    mass: 100.0,
+                    radius: 3.0,
+                    color: Color::CYAN,
+                    position: Vec3::new(100.0, 0.1, 0.0),
+                    velocity: Vec3::new(0.0, 0.0, 3.2),
+                    parent: None,
+                },
+                BodySpec {
+                    name: "Right".to_string(),
+                    mass: 2.0,
+                    radius: 1.0,
+                    color: Color::GREEN,
+                    position: Vec3::new(105.0, 0.1, 0.0),
+                    velocity: Vec3::new(0.0, 0.0, 2.7),
+                    parent: None,
+                },
+            ],
+            Scenario::InnerSolarSystem => {
+                const SUN_MASS: f32 = 10000.0;
+                const AU: f32 = 100.0;
+                let planet = |name: &str, mass_ratio: f32, radius, color, au: f32| {
+                    let position = Vec3::X * au * AU;
+                    BodySpec {
+                        name: name.to_string(),
+                        mass: SUN_MASS * mass_ratio,
+                        radius,
+                        color,
+                        position,
+                        velocity: circular_orbit_velocity(g, SUN_MASS, position),
+                        parent: None,
+                    }
+                };
+                vec![
+                    BodySpec {
+                        name: "Sun".to_string(),
+                        mass: SUN_MASS,
+                        radius: 8.0,
+                        color: Color::YELLOW,
+                        position: Vec3::ZERO,
+                        velocity: Vec3::ZERO,
+                        parent: None,
+                    },
+                    planet("Mercury", 1.66e-7, 1.0, Color::GRAY, 0.387),
+                    planet("Venus", 2.45e-6, 1.8, Color::BEIGE, 0.723),
+                    planet("Earth", 3.0e-6, 2.0, Color::BLUE, 1.0),
+                    planet("Mars", 3.23e-7, 1.5, Color::ORANGE_RED, 1.524),
+                ]
+            }
+            Scenario::FigureEight => {
+                let speed_scale = FIGURE_EIGHT_LENGTH / FIGURE_EIGHT_TIME;
+                let position = Vec3::new(0.970_004_4, 0.0, -0.243_087_5) * FIGURE_EIGHT_LENGTH;
+                let velocity = Vec3::new(-0.932_407_4, 0.0, -0.864_731_5) * speed_scale;
+                let body = |name: &str, color, position, velocity| BodySpec {
+                    name: name.to_string(),
+                    mass: FIGURE_EIGHT_MASS,
+                    radius: 3.0,
+                    color,
+                    position,
+                    velocity,
+                    parent: None,
+                };
+                vec![
+                    body("Alpha", Color::RED, position, -velocity / 2.0),
+                    body("Beta", Color::GREEN, -position, -velocity / 2.0),
+                    body("Gamma", Color::BLUE, Vec3::ZERO, velocity),
+                ]
+            }
+            Scenario::BinaryWithPlanet => {
+                const STAR_MASS: f32 = 1000.0;
+                const STAR_OFFSET: f32 = 30.0;
+                const PLANET_DISTANCE: f32 = 150.0;
+                // Each star orbits the barycenter at half their separation.
+                let star_speed = (g * STAR_MASS / (4.0 * STAR_OFFSET)).sqrt();
+                let planet_position = Vec3::X * PLANET_DISTANCE;
+                vec![
+                    BodySpec {
+                        name: "Star A".to_string(),
+                        mass: STAR_MASS,
+                        radius: 6.0,
+                        color: Color::ORANGE,
+                        position: Vec3::X * STAR_OFFSET,
+                        velocity: Vec3::Z * star_speed,
+                        parent: None,
+                    },
+                    BodySpec {
+                        name: "Star B".to_string(),
+                        mass: STAR_MASS,
+                        radius: 6.0,
+                        color: Color::ALICE_BLUE,
+                        position: Vec3::X * -STAR_OFFSET,
+                        velocity: Vec3::Z * -star_speed,
+                        parent: None,
+                    },
+                    BodySpec {
+                        name: "Planet".to_string(),
+                        mass: 1.0,
+                        radius: 2.0,
+                        color: Color::CYAN,
+                        position: planet_position,
+                        velocity: circular_orbit_velocity(g, 2.0 * STAR_MASS, planet_position),
+                        parent: None,
+                    },
+                ]
+            }
+            Scenario::EarthMoon => {
+                const SUN_MASS: f32 = 10000.0;
+                const EARTH_MASS: f32 = 10.0;
+                const EARTH_DISTANCE: f32 = 300.0;
+                const MOON_DISTANCE: f32 = 8.0;
+                let earth_position = on_circle(EARTH_DISTANCE, 0.0);
+                let moon_position = on_circle(MOON_DISTANCE, std::f32::consts::FRAC_PI_2);
+                vec![
+                    BodySpec {
+                        name: "Sun".to_string(),
+                        mass: SUN_MASS,
+                        radius: 8.0,
+                        color: Color::YELLOW,
+                        position: Vec3::ZERO,
+                        velocity: Vec3::ZERO,
+                        parent: None,
+                    },
+                    BodySpec {
+                        name: "Earth".to_string(),
+                        mass: EARTH_MASS,
+                        radius: 2.0,
+                        color: Color::BLUE,
+                        position: earth_position,
+                        velocity: circular_orbit_velocity(g, SUN_MASS, earth_position),
+                        parent: Some(0),
+                    },
+                    BodySpec {
+                        name: "Moon".to_string(),
+                        mass: 0.12,
+                        radius: 0.6,
+                        color: Color::GRAY,
+                        position: moon_position,
+                        velocity: circular_orbit_velocity(g, EARTH_MASS, moon_position),
+                        parent: Some(1),
+                    },
+                ]
+            }
+            Scenario::RealInnerPlanets => {
+                let units = self.units();
+                // Mass in kg, radius and orbit in m, the orbit taken as circular.
+                let planet = |name: &str, mass: f64, radius: f64, color, orbit: f64| {
+                    let speed = (GRAVITATIONAL_CONSTANT_SI * SUN_MASS_SI / orbit).sqrt();
+                    BodySpec {
+                        name: name.to_string(),
+                        mass: units.mass_from_si(mass) as f32,
+                        radius: units.length_from_si(radius * PLANET_RADIUS_SCALE) as f32,
+                        color,
+                        position: units.position_from_si(DVec3::X * orbit).as_vec3(),
+                        velocity: units.velocity_from_si(DVec3::Z * -speed).as_vec3(),
+                        parent: None,
+                    }
+                };
+                vec![
+                    BodySpec {
+                        name: "Sun".to_string(),
+                        mass: units.mass_from_si(SUN_MASS_SI) as f32,
+                        radius: units.length_from_si(6.957e8 * SUN_RADIUS_SCALE) as f32,
+                        color: Color::YELLOW,
+                        position: Vec3::ZERO,
+                        velocity: Vec3::ZERO,
+                        parent: None,
+                    },
+                    planet("Mercury", 3.301e23, 2.440e6, Color::GRAY, 5.791e10),
+                    planet("Venus", 4.867e24, 6.052e6, Color::BEIGE, 1.082e11),
+                    planet("Earth", 5.972e24, 6.371e6, Color::BLUE, 1.496e11),
+                    planet("Mars", 6.417e23, 3.390e6, Color::ORANGE_RED, 2.279e11),
+                ]
+            }
+        }
+    }
+}
+
+/// Spawns a single pickable body, constrained to the plane if planar mode is on.
+pub fn spawn_body(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    universe: &Universe,
+    body: BodySpec,
+) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 1.0,
+                subdivisions: 3,
+            })),
+            material: materials.add(body.color.into()),
+            transform: Transform::from_translation(universe.constrain(body.position))
+                .with_scale(Vec3::splat(body.radius)),
+            ..Default::default()
+        })
+        .insert(Name { name: body.name })
+        .insert(Celestial {
+            mass: body.mass,
+            velocity: universe.constrain(body.velocity),
+            charge: 0.0,
+            density: Celestial::density_for(body.mass, body.radius),
+        })
+        .insert(Radius(body.radius))
+        .insert(Spin::default())
+        .insert_bundle(PickableBundle::default())
+        .id()
+}
+
+/// Spawns every body of `scenario` and switches the universe to the
+/// gravitational constant and units it was tuned for. Bodies with a parent are placed
+/// relative to it and remember it as their frame.
+pub fn spawn_scenario(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    universe: &mut Universe,
+    units: &mut Units,
+    scenario: Scenario,
+) {
+    universe.gravitational_constant = scenario.gravitational_constant();
+    *units = scenario.units();
+    // Absolute position, velocity and entity of every body spawned so far, for
+    // the ones declared relative to a parent.
+    let mut spawned: Vec<(Vec3, Vec3, Entity)> = Vec::new();
+    for mut body in scenario.bodies() {
+        let parent = body.parent.and_then(|parent| spawned.get(parent).copied());
+        if let Some((position, velocity, _)) = parent {
+            body.position += position;
+            body.velocity += velocity;
+        }
+        let (position, velocity) = (body.position, body.velocity);
+        let entity = spawn_body(commands, meshes, materials, universe, body);
+        if let Some((_, _, parent)) = parent {
+            commands.entity(entity).insert(ParentBody(parent));
+        }
+        spawned.push((position, velocity, entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        physics::{step_celestial_map, CelestialBundle, CelestialMap, IntegratorKind},
+        UniverseTick,
+    };
+
+    #[test]
+    fn figure_eight_stays_bounded() {
+        let scenario = Scenario::FigureEight;
+        let constants = Universe {
+            gravitational_constant: scenario.gravitational_constant(),
+            integrator: IntegratorKind::Leapfrog,
+            ..default()
+        };
+        let mut celestial_map = CelestialMap {
+            map: scenario
+                .bodies()
+                .into_iter()
+                .enumerate()
+                .map(|(i, body)| {
+                    let bundle = CelestialBundle {
+                        pos: body.position.as_dvec3(),
+                        vel: body.velocity.as_dvec3(),
+                        acc: DVec3::ZERO,
+                        mass: body.mass as f64,
+                        charge: 0.0,
+                        pinned: false,
+                        radius: body.radius as f64,
+                        atmosphere: None,
+                    };
+                    (Entity::from_raw(i as u32), bundle)
+                })
+                .collect(),
+        };
+        // A little over two periods at the default step.
+        for _ in 0..3000 {
+            step_celestial_map(&mut celestial_map, &UniverseTick(0.016), &constants);
+            for body in celestial_map.map.values() {
+                assert!(
+                    body.pos.length() < 1.5 * FIGURE_EIGHT_LENGTH as f64,
+                    "{}",
+                    body.pos
+                );
+            }
+        }
+    }
+}
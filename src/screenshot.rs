@@ -0,0 +1,312 @@
+use std::{
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{Projection, RenderTarget},
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::{PrepareAssetLabel, RenderAssets},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        RenderApp, RenderStage,
+    },
+    tasks::IoTaskPool,
+};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::Inspectable;
+
+const SCREENSHOT_NODE: &str = "screenshot";
+
+#[derive(Inspectable)]
+pub struct ScreenshotSettings {
+    pub directory: String,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: "screenshots".to_string(),
+        }
+    }
+}
+
+/// An offscreen camera mirroring the main view for one frame. The render
+/// world copies its image into a buffer once it has been drawn.
+#[derive(Component, Clone)]
+struct ScreenshotRequest {
+    image: Handle<Image>,
+    width: u32,
+    height: u32,
+}
+
+impl ExtractComponent for ScreenshotRequest {
+    type Query = &'static Self;
+    type Filter = ();
+
+    fn extract_component(item: &Self) -> Self {
+        item.clone()
+    }
+}
+
+#[derive(Component)]
+struct ScreenshotReadback {
+    buffer: Buffer,
+    padded_bytes_per_row: u32,
+}
+
+struct CapturedFrame {
+    entity: Entity,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Frames read back by the render world, waiting to be picked up by the app.
+#[derive(Clone, Default)]
+struct CapturedFrames(Arc<Mutex<Vec<CapturedFrame>>>);
+
+fn request_screenshot(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    mut images: ResMut<Assets<Image>>,
+    main_camera: Query<(&Transform, &Projection), With<FlyCam>>,
+) {
+    if !keys.just_pressed(KeyCode::F12) {
+        return;
+    }
+    let (window, (transform, projection)) = match (windows.get_primary(), main_camera.get_single())
+    {
+        (Some(window), Ok(camera)) => (window, camera),
+        _ => return,
+    };
+    let (width, height) = (window.physical_width(), window.physical_height());
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // The main pass pipelines only render into the default format, which is
+    // BGRA on desktop, so the channels are put back in order when saving.
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::bevy_default(),
+    );
+    image.texture_descriptor.usage |= TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC;
+    let image = images.add(image);
+
+    commands
+        .spawn_bundle(Camera3dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(image.clone()),
+                priority: -1,
+                ..Default::default()
+            },
+            projection: projection.clone(),
+            transform: *transform,
+            ..Default::default()
+        })
+        .insert(ScreenshotRequest {
+            image,
+            width,
+            height,
+        });
+}
+
+fn prepare_readback(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<Image>>,
+    requests: Query<(Entity, &ScreenshotRequest)>,
+) {
+    for (entity, request) in requests.iter() {
+        // The camera has nothing to draw into until its image reaches the GPU.
+        if !gpu_images.contains_key(&request.image) {
+            continue;
+        }
+        let padded_bytes_per_row =
+            RenderDevice::align_copy_bytes_per_row(request.width as usize * 4) as u32;
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("screenshot_buffer"),
+            size: (padded_bytes_per_row * request.height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        commands.entity(entity).insert(ScreenshotReadback {
+            buffer,
+            padded_bytes_per_row,
+        });
+    }
+}
+
+struct ScreenshotNode {
+    query: QueryState<(&'static ScreenshotRequest, &'static ScreenshotReadback)>,
+}
+
+impl Node for ScreenshotNode {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let gpu_images = world.resource::<RenderAssets<Image>>();
+        for (request, readback) in self.query.iter_manual(world) {
+            let gpu_image = match gpu_images.get(&request.image) {
+                Some(gpu_image) => gpu_image,
+                None => continue,
+            };
+            render_context.command_encoder.copy_texture_to_buffer(
+                gpu_image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer: &readback.buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(readback.padded_bytes_per_row),
+                        rows_per_image: None,
+                    },
+                },
+                Extent3d {
+                    width: request.width,
+                    height: request.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Runs after the frame has been submitted, so the copies are done once the
+/// device has been polled.
+fn collect_readback(
+    render_device: Res<RenderDevice>,
+    captured: Res<CapturedFrames>,
+    readbacks: Query<(Entity, &ScreenshotRequest, &ScreenshotReadback)>,
+) {
+    for (entity, request, readback) in readbacks.iter() {
+        let slice = readback.buffer.slice(..);
+        render_device.map_buffer(&slice, MapMode::Read, |_| {});
+        render_device.poll(wgpu::Maintain::Wait);
+
+        let row_bytes = request.width as usize * 4;
+        let mut data: Vec<u8> = slice
+            .get_mapped_range()
+            .chunks(readback.padded_bytes_per_row as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+        readback.buffer.unmap();
+        if TextureFormat::bevy_default() == TextureFormat::Bgra8UnormSrgb {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        captured.0.lock().unwrap().push(CapturedFrame {
+            entity,
+            width: request.width,
+            height: request.height,
+            data,
+        });
+    }
+}
+
+fn screenshot_path(directory: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    Path::new(directory).join(format!("screenshot-{}.png", timestamp))
+}
+
+fn write_png(path: &Path, frame: &CapturedFrame) -> Result<(), String> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    }
+    image::save_buffer(
+        path,
+        &frame.data,
+        frame.width,
+        frame.height,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|err| err.to_string())
+}
+
+fn save_screenshots(
+    mut commands: Commands,
+    settings: Res<ScreenshotSettings>,
+    captured: Res<CapturedFrames>,
+    mut images: ResMut<Assets<Image>>,
+    requests: Query<&ScreenshotRequest>,
+) {
+    let frames: Vec<CapturedFrame> = captured.0.lock().unwrap().drain(..).collect();
+    for frame in frames {
+        if let Ok(request) = requests.get(frame.entity) {
+            images.remove(&request.image);
+            commands.entity(frame.entity).despawn();
+        }
+        let path = screenshot_path(&settings.directory);
+        IoTaskPool::get()
+            .spawn(async move {
+                match write_png(&path, &frame) {
+                    Ok(()) => info!("saved screenshot to {}", path.display()),
+                    Err(err) => error!("failed to save screenshot to {}: {}", path.display(), err),
+                }
+            })
+            .detach();
+    }
+}
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        let captured = CapturedFrames::default();
+        app.init_resource::<ScreenshotSettings>()
+            .insert_resource(captured.clone())
+            .add_plugin(ExtractComponentPlugin::<ScreenshotRequest>::default())
+            .add_system(request_screenshot)
+            .add_system(save_screenshots);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .insert_resource(captured)
+            .add_system_to_stage(
+                RenderStage::Prepare,
+                prepare_readback.after(PrepareAssetLabel::AssetPrepare),
+            )
+            .add_system_to_stage(RenderStage::Cleanup, collect_readback);
+
+        let node = ScreenshotNode {
+            query: QueryState::new(&mut render_app.world),
+        };
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(SCREENSHOT_NODE, node);
+        graph.add_node_edge(CAMERA_DRIVER, SCREENSHOT_NODE).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{PickingEvent, Selection};
+
+use crate::{Celestial, DebugMarker, InspectTarget, Name};
+
+pub struct SelectionChanged {
+    pub previous: Option<Entity>,
+    pub current: Option<Entity>,
+}
+
+/// The body selected before the current one. Together they make the pair
+/// that warps and closest approaches work on.
+#[derive(Default)]
+pub struct PreviousSelection(pub Option<Entity>);
+
+fn track_previous_selection(
+    mut selection_changed: EventReader<SelectionChanged>,
+    mut previous: ResMut<PreviousSelection>,
+) {
+    for event in selection_changed.iter() {
+        if event.previous.is_some() {
+            previous.0 = event.previous;
+        }
+    }
+}
+
+fn select(
+    inspector: &mut InspectTarget,
+    target: Option<Entity>,
+    selection_changed: &mut EventWriter<SelectionChanged>,
+) {
+    if inspector.target == target {
+        return;
+    }
+    selection_changed.send(SelectionChanged {
+        previous: inspector.target,
+        current: target,
+    });
+    inspector.target = target;
+}
+
+pub fn pick_active(
+    mut events: EventReader<PickingEvent>,
+    mut inspector: ResMut<InspectTarget>,
+    mut selection_changed: EventWriter<SelectionChanged>,
+) {
+    for event in events.iter() {
+        if let PickingEvent::Clicked(e) = event {
+            select(&mut inspector, Some(*e), &mut selection_changed);
+        }
+    }
+}
+
+fn cycle_selection(
+    keys: Res<Input<KeyCode>>,
+    bodies: Query<(Entity, Option<&Name>), (With<Celestial>, Without<DebugMarker>)>,
+    mut inspector: ResMut<InspectTarget>,
+    mut selection_changed: EventWriter<SelectionChanged>,
+) {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let backwards = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+
+    let mut ordered: Vec<(String, Entity)> = bodies
+        .iter()
+        .map(|(entity, name)| (name.map(|n| n.name.clone()).unwrap_or_default(), entity))
+        .collect();
+    if ordered.is_empty() {
+        return;
+    }
+    ordered.sort();
+
+    let current = inspector
+        .target
+        .and_then(|target| ordered.iter().position(|(_, entity)| *entity == target));
+    let next = match (current, backwards) {
+        (None, false) => 0,
+        (None, true) => ordered.len() - 1,
+        (Some(i), false) => (i + 1) % ordered.len(),
+        (Some(i), true) => (i + ordered.len() - 1) % ordered.len(),
+    };
+    select(
+        &mut inspector,
+        Some(ordered[next].1),
+        &mut selection_changed,
+    );
+}
+
+/// Mirrors the inspector target onto the picking `Selection` component so
+/// bodies selected from the keyboard are highlighted like clicked ones.
+fn sync_picking_selection(
+    mut selection_changed: EventReader<SelectionChanged>,
+    mut selections: Query<&mut Selection>,
+) {
+    for event in selection_changed.iter() {
+        if let Some(mut selection) = event.previous.and_then(|e| selections.get_mut(e).ok()) {
+            selection.set_selected(false);
+        }
+        if let Some(mut selection) = event.current.and_then(|e| selections.get_mut(e).ok()) {
+            selection.set_selected(true);
+        }
+    }
+}
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SelectionChanged>()
+            .init_resource::<PreviousSelection>()
+            .add_system(track_previous_selection)
+            .add_system(pick_active)
+            .add_system(cycle_selection)
+            .add_system(
+                sync_picking_selection
+                    .after(pick_active)
+                    .after(cycle_selection),
+            );
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::{math::DVec3, prelude::*};
+
+use crate::{
+    build_celestial_maps,
+    origin::SimState,
+    physics::{calculate_dt_velocity, CelestialBundle},
+    Celestial, DebugMarker, Universe,
+};
+
+/// Where a body's gravity dominates that of the body it orbits, and which body
+/// dominates it in turn. The heaviest body has no parent and an unbounded
+/// sphere.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SphereOfInfluence {
+    pub radius: f64,
+    pub dominant: Option<Entity>,
+}
+
+/// Laplace's sphere of influence of a body of `mass` at `distance` from a
+/// parent of `parent_mass`.
+pub fn soi_radius(distance: f64, mass: f64, parent_mass: f64) -> f64 {
+    distance * (mass / parent_mass).powf(0.4)
+}
+
+/// A body's place in the sphere of influence hierarchy, by index into the
+/// slice it was computed from.
+#[derive(Clone, Copy, Debug)]
+pub struct Influence {
+    pub parent: Option<usize>,
+    pub radius: f64,
+}
+
+/// Indices of `bodies`, heaviest first. Ties keep their original order.
+fn by_mass(bodies: &[CelestialBundle]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_by(|a, b| bodies[*b].mass.total_cmp(&bodies[*a].mass));
+    order
+}
+
+/// Assigns every body to the smallest sphere of influence of a heavier body
+/// that contains it, working down from the heaviest body.
+pub fn influence_hierarchy(bodies: &[CelestialBundle]) -> Vec<Influence> {
+    let mut hierarchy = vec![
+        Influence {
+            parent: None,
+            radius: f64::INFINITY,
+        };
+        bodies.len()
+    ];
+    let order = by_mass(bodies);
+    for (rank, &this) in order.iter().enumerate() {
+        let parent = order[..rank]
+            .iter()
+            .copied()
+            .filter(|&that| bodies[this].pos.distance(bodies[that].pos) < hierarchy[that].radius)
+            .min_by(|a, b| hierarchy[*a].radius.total_cmp(&hierarchy[*b].radius));
+        if let Some(parent) = parent {
+            hierarchy[this] = Influence {
+                parent: Some(parent),
+                radius: soi_radius(
+                    bodies[this].pos.distance(bodies[parent].pos),
+                    bodies[this].mass,
+                    bodies[parent].mass,
+                ),
+            };
+        }
+    }
+    hierarchy
+}
+
+/// Each body accelerates with its parent, plus its parent's pull on it. Bodies
+/// without a parent don't accelerate at all.
+pub fn patched_conic_accelerations(
+    constants: &Universe,
+    hierarchy: &[Influence],
+    bodies: &[CelestialBundle],
+) -> Vec<DVec3> {
+    let mut accelerations = vec![DVec3::ZERO; bodies.len()];
+    // Parents are always heavier, so they are done before their children.
+    for this in by_mass(bodies) {
+        if let Some(parent) = hierarchy[this].parent {
+            let pull = calculate_dt_velocity(
+                constants.gravitational_constant as f64,
+                constants.softening as f64,
+                bodies[this].pos,
+                bodies[parent].pos,
+                bodies[parent].mass,
+            );
+            accelerations[this] = constants.constrain_f64(accelerations[parent] + pull);
+        }
+    }
+    accelerations
+}
+
+/// Recomputed whenever a body moves, or appears without a sphere yet.
+fn update_spheres_of_influence(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    missing: Query<(), (With<SimState>, Without<SphereOfInfluence>)>,
+    mut bodies: ParamSet<(
+        Query<(), Or<(Changed<SimState>, Changed<Celestial>)>>,
+        Query<(Entity, &mut Celestial, &mut Transform, &mut SimState), Without<DebugMarker>>,
+    )>,
+) {
+    if bodies.p0().is_empty() && missing.is_empty() {
+        return;
+    }
+    let celestial_map = build_celestial_maps(&bodies.p1());
+    let entities = celestial_map.entities(universe.deterministic);
+    let states: Vec<CelestialBundle> = entities.iter().map(|e| celestial_map.map[e]).collect();
+    for (entity, influence) in entities.iter().zip(influence_hierarchy(&states)) {
+        commands.entity(*entity).insert(SphereOfInfluence {
+            radius: influence.radius,
+            dominant: influence.parent.map(|parent| entities[parent]),
+        });
+    }
+}
+
+pub struct SphereOfInfluencePlugin;
+
+impl Plugin for SphereOfInfluencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, update_spheres_of_influence);
+    }
+}
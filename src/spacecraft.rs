@@ -0,0 +1,204 @@
+use bevy::{ecs::schedule::RunCriteria, prelude::*};
+use bevy_flycam::FlyCam;
+use bevy_inspector_egui::{bevy_egui::EguiContext, egui, Inspectable, RegisterInspectable};
+
+use crate::{
+    origin::SimState,
+    pin::Pinned,
+    recording::not_replaying,
+    scenario::{spawn_body, BodySpec},
+    spin::Spin,
+    step_universe, Celestial, InspectTarget, Name, Universe, UniverseStep,
+};
+
+/// A body that can be flown. It feels gravity like any other body, and on top
+/// of that accelerates along its facing direction while the throttle is open.
+#[derive(Component, Clone, Copy, Inspectable)]
+pub struct Spacecraft {
+    /// Acceleration at full throttle.
+    #[inspectable(min = 0.0)]
+    pub thrust: f32,
+    /// Radians per second.
+    #[inspectable(min = 0.0)]
+    pub turn_rate: f32,
+    #[inspectable(min = 0.0, max = 1.0)]
+    pub throttle: f32,
+}
+
+impl Default for Spacecraft {
+    fn default() -> Self {
+        Self {
+            thrust: 5.0,
+            turn_rate: 1.5,
+            throttle: 0.0,
+        }
+    }
+}
+
+/// Elongated along -Z, which is the direction `Transform::forward` points.
+pub fn hull_mesh() -> Mesh {
+    Mesh::from(shape::Box::new(0.6, 0.6, 2.0))
+}
+
+/// Places a spacecraft in front of the camera, facing the same way, with J.
+fn spawn_spacecraft(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    universe: Res<Universe>,
+    mut spawned: Local<u32>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera: Query<&Transform, With<FlyCam>>,
+) {
+    if !keys.just_pressed(KeyCode::J) {
+        return;
+    }
+    let camera = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    *spawned += 1;
+    let radius = 1.0;
+    let position = universe.constrain(camera.translation + camera.forward() * radius * 10.0);
+    // Level with the XZ plane, so a camera looking straight down still gives
+    // the craft a usable heading.
+    let heading = Vec3::new(camera.forward().x, 0.0, camera.forward().z)
+        .try_normalize()
+        .unwrap_or(Vec3::NEG_Z);
+    let entity = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &universe,
+        BodySpec {
+            name: format!("Spacecraft {}", *spawned),
+            mass: 0.001,
+            radius,
+            color: Color::SILVER,
+            position,
+            velocity: Vec3::ZERO,
+            parent: None,
+        },
+    );
+    commands
+        .entity(entity)
+        .insert(meshes.add(hull_mesh()))
+        .insert(
+            Transform::from_translation(position)
+                .looking_at(position + heading, Vec3::Y)
+                .with_scale(Vec3::splat(radius)),
+        )
+        .insert(Spacecraft::default())
+        .remove::<Spin>();
+}
+
+/// Arrow keys or the left stick turn the craft, right shift or the right
+/// trigger open the throttle. In planar mode the craft only yaws. Only the
+/// selected craft is flown; the others coast with their throttle closed.
+fn steer_spacecraft(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    triggers: Res<Axis<GamepadButton>>,
+    universe: Res<Universe>,
+    inspector: Res<InspectTarget>,
+    mut crafts: Query<(Entity, &mut Spacecraft, &mut Transform)>,
+) {
+    let key_axis = |positive: KeyCode, negative: KeyCode| {
+        keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+    };
+    let mut yaw = key_axis(KeyCode::Left, KeyCode::Right);
+    let mut pitch = key_axis(KeyCode::Up, KeyCode::Down);
+    let mut throttle: f32 = if keys.pressed(KeyCode::RShift) {
+        1.0
+    } else {
+        0.0
+    };
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(*gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        yaw -= axis(GamepadAxisType::LeftStickX);
+        pitch += axis(GamepadAxisType::LeftStickY);
+        throttle = throttle.max(
+            triggers
+                .get(GamepadButton::new(
+                    *gamepad,
+                    GamepadButtonType::RightTrigger2,
+                ))
+                .unwrap_or(0.0),
+        );
+    }
+    if universe.planar {
+        pitch = 0.0;
+    }
+
+    for (entity, mut craft, mut transform) in crafts.iter_mut() {
+        if inspector.target != Some(entity) {
+            craft.throttle = 0.0;
+            continue;
+        }
+        let turn = craft.turn_rate * time.delta_seconds();
+        let rotation = Quat::from_axis_angle(transform.up(), yaw.clamp(-1.0, 1.0) * turn)
+            * Quat::from_axis_angle(transform.right(), pitch.clamp(-1.0, 1.0) * turn);
+        transform.rotate(rotation);
+        craft.throttle = throttle.clamp(0.0, 1.0);
+    }
+}
+
+/// Adds each step's worth of thrust to the velocity before the simulation
+/// steps, so the burn is integrated in simulation time rather than frame time.
+fn apply_thrust(
+    universe: Res<Universe>,
+    mut crafts: Query<(&Spacecraft, &Transform, &mut Celestial, &mut SimState), Without<Pinned>>,
+) {
+    let dt = universe.tick().0;
+    for (craft, transform, mut body, mut state) in crafts.iter_mut() {
+        if craft.throttle <= 0.0 {
+            continue;
+        }
+        let delta = universe.constrain(transform.forward() * craft.thrust * craft.throttle * dt);
+        state.velocity += delta.as_dvec3();
+        body.velocity = state.velocity.as_vec3();
+    }
+}
+
+fn spacecraft_hud(
+    mut egui_context: ResMut<EguiContext>,
+    crafts: Query<(&Spacecraft, &Celestial, Option<&Name>)>,
+) {
+    if crafts.is_empty() {
+        return;
+    }
+    egui::Window::new("Spacecraft").show(egui_context.ctx_mut(), |ui| {
+        for (craft, body, name) in crafts.iter() {
+            if let Some(name) = name {
+                ui.label(&name.name);
+            }
+            ui.add(
+                egui::ProgressBar::new(craft.throttle)
+                    .text(format!("Thrust {:.0}%", craft.throttle * 100.0)),
+            );
+            ui.label(format!("Speed {:.3}", body.velocity.length()));
+        }
+    });
+}
+
+pub struct SpacecraftPlugin;
+
+impl Plugin for SpacecraftPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<Spacecraft>()
+            .add_system(spawn_spacecraft)
+            .add_system(steer_spacecraft)
+            .add_system(
+                apply_thrust
+                    .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                    .after(steer_spacecraft)
+                    .before(step_universe),
+            )
+            .add_system(spacecraft_hud);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::Universe;
+
+/// Rotation of a body about its own axis. It's purely visual, so it runs every
+/// frame rather than on the simulation tick.
+#[derive(Component, Clone, Copy, Inspectable)]
+pub struct Spin {
+    pub axis: Vec3,
+    /// Radians per second.
+    #[inspectable(speed = 0.01)]
+    pub angular_velocity: f32,
+}
+
+impl Default for Spin {
+    fn default() -> Self {
+        Self {
+            axis: Vec3::Y,
+            angular_velocity: 0.5,
+        }
+    }
+}
+
+fn spin_bodies(
+    time: Res<Time>,
+    universe: Res<Universe>,
+    mut bodies: Query<(&Spin, &mut Transform)>,
+) {
+    if !universe.active {
+        return;
+    }
+    for (spin, mut transform) in bodies.iter_mut() {
+        let axis = match spin.axis.try_normalize() {
+            Some(axis) => axis,
+            None => continue,
+        };
+        transform.rotate(Quat::from_axis_angle(
+            axis,
+            spin.angular_velocity * time.delta_seconds(),
+        ));
+    }
+}
+
+pub struct SpinPlugin;
+
+impl Plugin for SpinPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_inspectable::<Spin>().add_system(spin_bodies);
+    }
+}
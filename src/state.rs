@@ -0,0 +1,130 @@
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use bevy_inspector_egui::{
+    bevy_egui::EguiContext,
+    egui::{self, Align2},
+};
+use bevy_mod_picking::PickingPluginsState;
+
+use crate::{
+    scenario::{LoadScenarioEvent, Scenario},
+    Universe,
+};
+
+/// Where the app is. `Paused` and `Running` follow `Universe::active`, so
+/// every way of pausing keeps them in step; the menu holds the simulation
+/// still whatever `active` says. F1 goes back to the menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Menu,
+    Running,
+    Paused,
+}
+
+/// Run criterion for input and debug systems that have nothing to do while
+/// the menu is up. Apps without the menu, like the headless runner, are
+/// never in it.
+pub fn outside_menu(state: Option<Res<State<AppState>>>) -> ShouldRun {
+    match state {
+        Some(state) if *state.current() == AppState::Menu => ShouldRun::No,
+        _ => ShouldRun::Yes,
+    }
+}
+
+fn menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<State<AppState>>,
+    mut universe: ResMut<Universe>,
+    mut load_scenario: EventWriter<LoadScenarioEvent>,
+) {
+    egui::Window::new("Menu")
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            for scenario in Scenario::ALL {
+                if ui
+                    .radio(universe.scenario == scenario, format!("{:?}", scenario))
+                    .clicked()
+                    && universe.scenario != scenario
+                {
+                    universe.scenario = scenario;
+                    load_scenario.send(LoadScenarioEvent);
+                }
+            }
+            ui.separator();
+            if ui.button("Start").clicked() {
+                universe.active = true;
+                // Already queued if F1 was pressed this frame too.
+                let _ = state.set(AppState::Running);
+            }
+        });
+}
+
+fn open_menu(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<AppState>>,
+    mut universe: ResMut<Universe>,
+) {
+    if keys.just_pressed(KeyCode::F1) {
+        universe.active = false;
+        let _ = state.set(AppState::Menu);
+    }
+}
+
+fn follow_active(universe: Res<Universe>, mut state: ResMut<State<AppState>>) {
+    let next = match (state.current(), universe.active) {
+        (AppState::Running, false) => AppState::Paused,
+        (AppState::Paused, true) => AppState::Running,
+        _ => return,
+    };
+    let _ = state.set(next);
+}
+
+fn pause_overlay(mut egui_context: ResMut<EguiContext>) {
+    egui::Area::new("pause_overlay")
+        .anchor(Align2::CENTER_TOP, [0.0, 40.0])
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading("Paused");
+            ui.label("U to resume, T to step");
+        });
+}
+
+fn enter_menu(mut picking: ResMut<PickingPluginsState>) {
+    picking.enable_picking = false;
+    picking.enable_highlighting = false;
+    picking.enable_interacting = false;
+}
+
+fn exit_menu(mut picking: ResMut<PickingPluginsState>) {
+    *picking = PickingPluginsState::default();
+}
+
+/// The flycam only moves while the cursor is grabbed, so letting go of it
+/// every frame keeps the camera still. Esc would otherwise grab it again.
+fn release_cursor(mut windows: ResMut<Windows>) {
+    if let Some(window) = windows.get_primary_mut() {
+        if window.cursor_locked() {
+            window.set_cursor_lock_mode(false);
+            window.set_cursor_visibility(true);
+        }
+    }
+}
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Menu)
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(enter_menu))
+            .add_system_set(SystemSet::on_exit(AppState::Menu).with_system(exit_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::Menu)
+                    .with_system(menu)
+                    .with_system(release_cursor),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_overlay))
+            .add_system(open_menu)
+            .add_system(follow_active.with_run_criteria(outside_menu));
+    }
+}
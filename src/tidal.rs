@@ -0,0 +1,148 @@
+use bevy::{ecs::schedule::RunCriteria, math::DVec3, prelude::*};
+use bevy_mod_picking::Highlighting;
+
+use crate::{
+    appearance::base_material,
+    collision::resolve_collisions,
+    origin::{FloatingOrigin, SimState},
+    particles::TestParticle,
+    pin::Pinned,
+    recording::not_replaying,
+    scenario::{spawn_body, BodySpec},
+    spacecraft::Spacecraft,
+    step_universe, Celestial, DebugMarker, Name, Radius, Universe, UniverseStep,
+};
+
+/// Left behind by a tidal breakup. Fragments don't break up again, otherwise
+/// they would keep splitting for as long as they stay inside the limit.
+#[derive(Component)]
+pub struct Fragment;
+
+/// Distance from a primary of `primary_radius` and `primary_density` inside
+/// which a rigid satellite of `satellite_density` is pulled apart by tides.
+pub fn roche_limit(primary_radius: f32, primary_density: f32, satellite_density: f32) -> f32 {
+    primary_radius * (2.0 * primary_density / satellite_density).cbrt()
+}
+
+struct Breakup {
+    entity: Entity,
+    /// Unit vector pointing from the primary to the satellite.
+    axis: DVec3,
+}
+
+/// Splits bodies that strayed inside the Roche limit of a heavier body into
+/// `fragment_count` pieces strung out along the line to the primary, the way
+/// tides stretch them. Fragments share the original mass and density equally
+/// and spread apart at the body's own dynamical rate, symmetrically, so
+/// momentum is conserved. Spacecraft are too small and sturdy for tides to
+/// matter. Distances are measured in simulation space, and the fragments start
+/// out with their simulation state already set from it.
+fn break_up_bodies(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    origin: Res<FloatingOrigin>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<
+        (
+            Entity,
+            &Celestial,
+            &SimState,
+            &Radius,
+            &Handle<StandardMaterial>,
+            Option<&Highlighting<StandardMaterial>>,
+            Option<&Name>,
+        ),
+        (
+            Without<DebugMarker>,
+            Without<Pinned>,
+            Without<Fragment>,
+            Without<Spacecraft>,
+            Without<TestParticle>,
+        ),
+    >,
+    primaries: Query<(Entity, &Celestial, &SimState, &Radius), Without<DebugMarker>>,
+) {
+    if !universe.tidal_breakup {
+        return;
+    }
+
+    let mut breakups = Vec::new();
+    for (entity, body, state, ..) in bodies.iter() {
+        let breaks = primaries
+            .iter()
+            .filter(|(other, primary, ..)| *other != entity && primary.mass > body.mass)
+            .find(|(_, primary, primary_state, primary_radius)| {
+                let distance = state.position.distance(primary_state.position);
+                distance < roche_limit(primary_radius.0, primary.density, body.density) as f64
+            });
+        if let Some((_, _, primary_state, _)) = breaks {
+            let axis = (state.position - primary_state.position)
+                .try_normalize()
+                .unwrap_or(DVec3::X);
+            breakups.push(Breakup { entity, axis });
+        }
+    }
+
+    let count = universe.fragment_count.max(2);
+    for breakup in breakups {
+        let (entity, body, state, radius, material, highlighting, name) =
+            bodies.get(breakup.entity).unwrap();
+        let color = materials
+            .get(base_material(material, highlighting))
+            .map_or(Color::WHITE, |material| material.base_color);
+        let name = name.map_or_else(|| format!("{:?}", entity), |name| name.name.clone());
+        let mass = body.mass / count as f32;
+        let fragment_radius = radius.0 / (count as f32).cbrt();
+        // Just far enough apart that neighbors don't start out touching.
+        let spacing = 2.1 * fragment_radius as f64;
+        let spread_rate = (universe.gravitational_constant as f64 * body.mass as f64
+            / (radius.0 as f64).powi(3))
+        .max(0.0)
+        .sqrt();
+
+        for i in 0..count {
+            let offset = (i as f64 - (count - 1) as f64 / 2.0) * spacing;
+            let position = state.position + breakup.axis * offset;
+            let velocity = state.velocity + breakup.axis * offset * spread_rate;
+            let fragment = spawn_body(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &universe,
+                BodySpec {
+                    name: format!("{} fragment {}", name, i + 1),
+                    mass,
+                    radius: fragment_radius,
+                    color,
+                    position: origin.to_render(position),
+                    velocity: velocity.as_vec3(),
+                    parent: None,
+                },
+            );
+            commands
+                .entity(fragment)
+                .insert(SimState {
+                    position,
+                    velocity,
+                    acceleration: DVec3::ZERO,
+                })
+                .insert(Fragment);
+        }
+        commands.entity(entity).despawn();
+        info!("{} broke up into {} fragments", name, count);
+    }
+}
+
+pub struct TidalPlugin;
+
+impl Plugin for TidalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            break_up_bodies
+                .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                .after(step_universe)
+                .before(resolve_collisions),
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+use bevy::{ecs::schedule::RunCriteria, math::DVec3, prelude::*};
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    origin::FloatingOrigin,
+    recording::not_replaying,
+    scenario::{spawn_body, BodySpec},
+    step_universe, Celestial, Name, SimClock, Universe, UniverseStep,
+};
+
+#[derive(Inspectable)]
+pub struct TimelineSettings {
+    pub path: String,
+}
+
+impl Default for TimelineSettings {
+    fn default() -> Self {
+        Self {
+            path: "timeline.csv".to_string(),
+        }
+    }
+}
+
+/// Reads `TimelineSettings::path` again, replacing the current timeline.
+#[derive(Default)]
+pub struct LoadTimelineEvent;
+
+enum Action {
+    Spawn {
+        name: String,
+        mass: f32,
+        radius: f32,
+        color: Color,
+        position: DVec3,
+        velocity: DVec3,
+    },
+    SetMass {
+        name: String,
+        mass: f32,
+    },
+}
+
+struct ScheduledEvent {
+    time: f64,
+    action: Action,
+}
+
+/// Things to do to the scenario at set points on the simulation clock, sorted
+/// by time. The file has one event per line, times in simulation seconds and
+/// bodies referred to by name:
+///
+/// ```text
+/// spawn,time,mass,radius,r,g,b,x,y,z,vx,vy,vz,name
+/// mass,time,mass,name
+/// ```
+///
+/// Names go last so they can hold commas. Blank lines and lines starting with
+/// `#` are skipped.
+#[derive(Default)]
+pub struct Timeline {
+    events: Vec<ScheduledEvent>,
+    next: usize,
+    last_elapsed: f64,
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed timeline line: {}", line),
+    )
+}
+
+fn parse_fields<T: std::str::FromStr>(fields: &[&str], line: &str) -> io::Result<Vec<T>> {
+    fields
+        .iter()
+        .map(|field| field.parse().map_err(|_| invalid_line(line)))
+        .collect()
+}
+
+fn read_timeline(path: &str) -> io::Result<Timeline> {
+    let mut events = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let kind = line.split(',').next().unwrap_or_default();
+        let event = match kind {
+            "spawn" => {
+                let fields: Vec<&str> = line.splitn(14, ',').collect();
+                if fields.len() != 14 {
+                    return Err(invalid_line(&line));
+                }
+                let values: Vec<f64> = parse_fields(&fields[1..13], &line)?;
+                ScheduledEvent {
+                    time: values[0],
+                    action: Action::Spawn {
+                        name: fields[13].to_string(),
+                        mass: values[1] as f32,
+                        radius: values[2] as f32,
+                        color: Color::rgb(values[3] as f32, values[4] as f32, values[5] as f32),
+                        position: DVec3::new(values[6], values[7], values[8]),
+                        velocity: DVec3::new(values[9], values[10], values[11]),
+                    },
+                }
+            }
+            "mass" => {
+                let fields: Vec<&str> = line.splitn(4, ',').collect();
+                if fields.len() != 4 {
+                    return Err(invalid_line(&line));
+                }
+                let values: Vec<f64> = parse_fields(&fields[1..3], &line)?;
+                ScheduledEvent {
+                    time: values[0],
+                    action: Action::SetMass {
+                        name: fields[3].to_string(),
+                        mass: values[1] as f32,
+                    },
+                }
+            }
+            _ => return Err(invalid_line(&line)),
+        };
+        events.push(event);
+    }
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(Timeline {
+        events,
+        ..Timeline::default()
+    })
+}
+
+/// Loads the timeline at startup if the file is there, and again whenever
+/// asked to.
+fn load_timeline(
+    mut events: EventReader<LoadTimelineEvent>,
+    settings: Res<TimelineSettings>,
+    clock: Res<SimClock>,
+    mut timeline: ResMut<Timeline>,
+    mut loaded: Local<bool>,
+) {
+    let requested = events.iter().count() > 0;
+    if *loaded && !requested {
+        return;
+    }
+    *loaded = true;
+    match read_timeline(&settings.path) {
+        Ok(mut read) => {
+            read.next = read
+                .events
+                .partition_point(|event| event.time < clock.elapsed_seconds);
+            read.last_elapsed = clock.elapsed_seconds;
+            info!(
+                "loaded {} timeline events from {}",
+                read.events.len(),
+                settings.path
+            );
+            *timeline = read;
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !requested => {}
+        Err(err) => error!("failed to read timeline {}: {}", settings.path, err),
+    }
+}
+
+/// Runs every event the clock has reached. When the clock goes back, through
+/// a reset, a rewind or a checkpoint, the events after the new time are armed
+/// again so they replay the same way.
+fn run_timeline(
+    mut commands: Commands,
+    universe: Res<Universe>,
+    clock: Res<SimClock>,
+    origin: Res<FloatingOrigin>,
+    mut timeline: ResMut<Timeline>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bodies: Query<(&Name, &mut Celestial)>,
+) {
+    let now = clock.elapsed_seconds;
+    if now < timeline.last_elapsed {
+        timeline.next = timeline.events.partition_point(|event| event.time < now);
+    }
+    timeline.last_elapsed = now;
+    while let Some(event) = timeline.events.get(timeline.next) {
+        if event.time > now {
+            break;
+        }
+        match &event.action {
+            Action::Spawn {
+                name,
+                mass,
+                radius,
+                color,
+                position,
+                velocity,
+            } => {
+                spawn_body(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &universe,
+                    BodySpec {
+                        name: name.clone(),
+                        mass: *mass,
+                        radius: *radius,
+                        color: *color,
+                        position: origin.to_render(*position),
+                        velocity: velocity.as_vec3(),
+                        parent: None,
+                    },
+                );
+                info!("t={:.2}: spawned {}", now, name);
+            }
+            Action::SetMass { name, mass } => {
+                match bodies
+                    .iter_mut()
+                    .find(|(body_name, _)| body_name.name == *name)
+                {
+                    Some((_, mut body)) => {
+                        body.mass = *mass;
+                        info!("t={:.2}: set the mass of {} to {}", now, name, mass);
+                    }
+                    None => warn!("t={:.2}: no body named {} to change", now, name),
+                }
+            }
+        }
+        timeline.next += 1;
+    }
+}
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimelineSettings>()
+            .init_resource::<Timeline>()
+            .add_event::<LoadTimelineEvent>()
+            .add_system(load_timeline)
+            .add_system(
+                run_timeline
+                    .with_run_criteria(RunCriteria::pipe(UniverseStep, not_replaying))
+                    .after(step_universe)
+                    .after(load_timeline),
+            );
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use bevy::{math::DVec3, prelude::*};
+use bevy_mod_picking::Highlighting;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    appearance::{base_material, segment_colors},
+    collision::{resolve_collisions, CollisionEvent, CollisionOutcome},
+    origin::{FloatingOrigin, SimState},
+    particles::TestParticle,
+    step_universe, Celestial, DebugMarker, SimClock, Universe, UniverseStep,
+};
+
+/// Positions a body has actually passed through, newest at the back. Kept in
+/// simulation space so they stay put when the render origin moves.
+#[derive(Component, Default)]
+pub struct Trail {
+    points: VecDeque<DVec3>,
+}
+
+impl Trail {
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
+
+/// Runs after every simulation step and samples every `trail_interval` of
+/// them, however many steps land in one frame.
+fn record_trails(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    universe: Res<Universe>,
+    mut last_sample: Local<u64>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut bodies: Query<
+        (Entity, &SimState, Option<&mut Trail>),
+        (With<Celestial>, Without<DebugMarker>, Without<TestParticle>),
+    >,
+) {
+    // The merged body jumps to the combined center of mass, so its old trail
+    // no longer leads up to it.
+    for event in collisions.iter() {
+        if let CollisionOutcome::Merged { survivor } = event.outcome {
+            if let Ok((_, _, Some(mut trail))) = bodies.get_mut(survivor) {
+                trail.clear();
+            }
+        }
+    }
+    if clock.step_count.abs_diff(*last_sample) < universe.trail_interval {
+        return;
+    }
+    *last_sample = clock.step_count;
+
+    for (entity, state, trail) in bodies.iter_mut() {
+        let mut trail = match trail {
+            Some(trail) => trail,
+            None => {
+                commands.entity(entity).insert(Trail::default());
+                continue;
+            }
+        };
+        trail.points.push_back(state.position);
+        while trail.points.len() > universe.trail_length {
+            trail.points.pop_front();
+        }
+    }
+}
+
+/// Debug lines are drawn opaque, so the tail fades into the clear color
+/// rather than becoming transparent.
+fn draw_trails(
+    universe: Res<Universe>,
+    clear_color: Res<ClearColor>,
+    origin: Res<FloatingOrigin>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut lines: ResMut<DebugLines>,
+    trails: Query<(
+        &Trail,
+        &Handle<StandardMaterial>,
+        Option<&Highlighting<StandardMaterial>>,
+    )>,
+) {
+    let background = Vec4::from(clear_color.0.as_rgba_f32());
+    for (trail, material, highlighting) in trails.iter() {
+        let color = match materials.get(base_material(material, highlighting)) {
+            Some(material) => material.base_color,
+            None => continue,
+        };
+        let points: Vec<DVec3> = trail.points.iter().copied().collect();
+        let colors = segment_colors(&universe, color, &points, false);
+        let count = points.len();
+        for (i, ((start, end), color)) in points
+            .iter()
+            .zip(points.iter().skip(1))
+            .zip(colors)
+            .enumerate()
+        {
+            let t = (i + 1) as f32 / count as f32;
+            let color = Vec4::from(color.as_rgba_f32());
+            let [r, g, b, _] = background.lerp(color, t).to_array();
+            lines.line_colored(
+                origin.to_render(*start),
+                origin.to_render(*end),
+                0.0,
+                Color::rgba(r, g, b, t),
+            );
+        }
+    }
+}
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            record_trails
+                .with_run_criteria(UniverseStep)
+                .after(step_universe)
+                .after(resolve_collisions),
+        )
+        .add_system(draw_trails.after(record_trails));
+    }
+}
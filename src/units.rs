@@ -0,0 +1,91 @@
+use bevy::math::DVec3;
+use bevy_inspector_egui::Inspectable;
+
+/// Newton's constant in m³ kg⁻¹ s⁻².
+pub const GRAVITATIONAL_CONSTANT_SI: f64 = 6.674_30e-11;
+
+/// How many meters, kilograms and seconds one simulation unit of length, mass
+/// and time stands for. The simulation itself only ever sees simulation units;
+/// this is for entering real data and reading results back out. Loading a
+/// scenario sets it to the units that scenario was written in.
+#[derive(Inspectable, Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    pub meters: f64,
+    pub kilograms: f64,
+    pub seconds: f64,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            meters: 1.0,
+            kilograms: 1.0,
+            seconds: 1.0,
+        }
+    }
+}
+
+impl Units {
+    /// A million kilometers, 10²⁴ kg (about a sixth of the Earth) and a
+    /// million seconds (about 11.6 days). Speeds come out in km/s and the inner
+    /// planets orbit at a comfortable pace.
+    pub const SOLAR_SYSTEM: Units = Units {
+        meters: 1.0e9,
+        kilograms: 1.0e24,
+        seconds: 1.0e6,
+    };
+
+    /// G expressed in these units.
+    pub fn gravitational_constant(&self) -> f64 {
+        GRAVITATIONAL_CONSTANT_SI * self.kilograms * self.seconds.powi(2) / self.meters.powi(3)
+    }
+
+    pub fn length_to_si(&self, length: f64) -> f64 {
+        length * self.meters
+    }
+
+    pub fn length_from_si(&self, meters: f64) -> f64 {
+        meters / self.meters
+    }
+
+    pub fn mass_to_si(&self, mass: f64) -> f64 {
+        mass * self.kilograms
+    }
+
+    pub fn mass_from_si(&self, kilograms: f64) -> f64 {
+        kilograms / self.kilograms
+    }
+
+    pub fn time_to_si(&self, time: f64) -> f64 {
+        time * self.seconds
+    }
+
+    pub fn speed_to_si(&self, speed: f64) -> f64 {
+        speed * self.meters / self.seconds
+    }
+
+    pub fn position_from_si(&self, meters: DVec3) -> DVec3 {
+        meters / self.meters
+    }
+
+    pub fn velocity_from_si(&self, meters_per_second: DVec3) -> DVec3 {
+        meters_per_second * self.seconds / self.meters
+    }
+}
+
+/// `seconds` in whichever of seconds, minutes, hours, days or years reads best.
+pub fn format_duration(seconds: f64) -> String {
+    const STEPS: [(f64, &str); 4] = [
+        (60.0, "min"),
+        (3600.0, "h"),
+        (86_400.0, "days"),
+        (31_557_600.0, "years"),
+    ];
+    let (scale, unit) = STEPS
+        .iter()
+        .rev()
+        .find(|(scale, _)| seconds.abs() >= *scale)
+        .copied()
+        .unwrap_or((1.0, "s"));
+    format!("{:.1} {}", seconds / scale, unit)
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    origin::SimState, overlay::draw_arrow, particles::TestParticle, Celestial, DebugMarker,
+    InspectTarget, Universe,
+};
+
+const VELOCITY_COLOR: Color = Color::GREEN;
+const ACCELERATION_COLOR: Color = Color::ORANGE_RED;
+
+/// Leaves a body out of the velocity and acceleration arrows while they're
+/// shown.
+#[derive(Component)]
+pub struct HideVectors;
+
+/// Arrow length for a vector of `magnitude`. Logarithmic, so slow bodies
+/// still get a visible arrow and fast ones don't reach across the scene.
+pub fn arrow_length(magnitude: f32, scale: f32) -> f32 {
+    scale * magnitude.ln_1p()
+}
+
+/// O shows or hides every velocity arrow and Z every acceleration arrow.
+/// Shift+O hides or shows just the selected body's arrows.
+fn toggle_vector_arrows(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    inspector: Res<InspectTarget>,
+    mut universe: ResMut<Universe>,
+    bodies: Query<Option<&HideVectors>, With<Celestial>>,
+) {
+    if keys.just_pressed(KeyCode::Z) {
+        universe.show_acceleration = !universe.show_acceleration;
+    }
+    if !keys.just_pressed(KeyCode::O) {
+        return;
+    }
+    if !keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        universe.show_velocity = !universe.show_velocity;
+        return;
+    }
+    let target = match inspector.target {
+        Some(target) => target,
+        None => return,
+    };
+    match bodies.get(target) {
+        Ok(Some(_)) => {
+            commands.entity(target).remove::<HideVectors>();
+        }
+        Ok(None) => {
+            commands.entity(target).insert(HideVectors);
+        }
+        Err(_) => {}
+    }
+}
+
+/// Accelerations come from the force evaluation the last step itself made,
+/// so they show exactly what the integrator used.
+fn draw_vector_arrows(
+    universe: Res<Universe>,
+    mut lines: ResMut<DebugLines>,
+    bodies: Query<
+        (&Celestial, &SimState, &Transform),
+        (
+            Without<HideVectors>,
+            Without<DebugMarker>,
+            Without<TestParticle>,
+        ),
+    >,
+) {
+    if !universe.show_velocity && !universe.show_acceleration {
+        return;
+    }
+    for (body, state, transform) in bodies.iter() {
+        let start = transform.translation;
+        if universe.show_velocity {
+            let length = arrow_length(body.velocity.length(), universe.vector_scale);
+            let end = start + body.velocity.normalize_or_zero() * length;
+            draw_arrow(&mut lines, start, end, VELOCITY_COLOR);
+        }
+        if universe.show_acceleration {
+            let acceleration = state.acceleration.as_vec3();
+            let length = arrow_length(
+                acceleration.length() * universe.acceleration_scale,
+                universe.vector_scale,
+            );
+            let end = start + acceleration.normalize_or_zero() * length;
+            draw_arrow(&mut lines, start, end, ACCELERATION_COLOR);
+        }
+    }
+}
+
+pub struct VectorPlugin;
+
+impl Plugin for VectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_vector_arrows)
+            .add_system(draw_vector_arrows.after(toggle_vector_arrows));
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+
+use crate::{
+    origin::SimState, selection::PreviousSelection, step_universe, InspectTarget, SimClock,
+    SimulationCommand, Universe, UniverseStep,
+};
+
+#[derive(Inspectable)]
+pub struct WarpSettings {
+    /// Simulation seconds F skips ahead by.
+    #[inspectable(min = 0.0)]
+    pub duration: f64,
+    /// Steps run every frame while warping. Higher warps faster but the
+    /// window redraws less often.
+    #[inspectable(min = 1)]
+    pub steps_per_frame: u32,
+    /// How many times longer each step is while warping.
+    #[inspectable(min = 1)]
+    pub step_multiplier: u64,
+}
+
+impl Default for WarpSettings {
+    fn default() -> Self {
+        Self {
+            duration: 10.0,
+            steps_per_frame: 500,
+            step_multiplier: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum WarpTarget {
+    /// A point on the simulation clock.
+    Time(f64),
+    /// The next time two bodies stop getting closer.
+    ClosestApproach(Entity, Entity),
+}
+
+/// A fast forward in progress. `simulation_step_ms` is stretched for the
+/// duration and put back when the target is reached.
+#[derive(Default)]
+pub struct Warp {
+    target: Option<WarpTarget>,
+    normal_step_ms: u64,
+    last_distance: Option<f64>,
+    approaching: bool,
+}
+
+impl Warp {
+    pub fn is_warping(&self) -> bool {
+        self.target.is_some()
+    }
+
+    fn start(&mut self, universe: &mut Universe, target: WarpTarget, multiplier: u64) {
+        self.target = Some(target);
+        self.normal_step_ms = universe.simulation_step_ms;
+        self.last_distance = None;
+        self.approaching = false;
+        universe.simulation_step_ms *= multiplier.max(1);
+        info!("warping until {:?}", target);
+    }
+
+    fn stop(&mut self, universe: &mut Universe) {
+        if self.target.take().is_some() {
+            universe.simulation_step_ms = self.normal_step_ms;
+        }
+    }
+}
+
+/// F warps `WarpSettings::duration` ahead, Shift+F warps to the closest
+/// approach between the selected body and the one selected before it.
+/// Pressing F again during a warp cancels it.
+fn start_warp(
+    keys: Res<Input<KeyCode>>,
+    settings: Res<WarpSettings>,
+    clock: Res<SimClock>,
+    previous: Res<PreviousSelection>,
+    inspector: Res<InspectTarget>,
+    mut universe: ResMut<Universe>,
+    mut warp: ResMut<Warp>,
+) {
+    if !keys.just_pressed(KeyCode::F) {
+        return;
+    }
+    if warp.is_warping() {
+        warp.stop(&mut universe);
+        info!("warp cancelled at {:.3}", clock.elapsed_seconds);
+        return;
+    }
+    let target = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        match (inspector.target, previous.0) {
+            (Some(a), Some(b)) if a != b => WarpTarget::ClosestApproach(a, b),
+            _ => {
+                info!("select two bodies to warp to their closest approach");
+                return;
+            }
+        }
+    } else {
+        let sign = universe.direction.sign() as f64;
+        WarpTarget::Time(clock.elapsed_seconds + sign * settings.duration)
+    };
+    warp.start(&mut universe, target, settings.step_multiplier);
+}
+
+fn drive_warp(
+    settings: Res<WarpSettings>,
+    warp: Res<Warp>,
+    mut simulation: EventWriter<SimulationCommand>,
+) {
+    if warp.is_warping() {
+        simulation.send(SimulationCommand::Step(settings.steps_per_frame));
+    }
+}
+
+/// Runs after every step and halts the remaining steps as soon as the target
+/// is passed.
+fn check_warp(
+    clock: Res<SimClock>,
+    mut universe: ResMut<Universe>,
+    mut warp: ResMut<Warp>,
+    mut simulation: EventWriter<SimulationCommand>,
+    bodies: Query<&SimState>,
+) {
+    let reached = match warp.target {
+        None => return,
+        Some(WarpTarget::Time(time)) => {
+            (clock.elapsed_seconds - time) * universe.direction.sign() as f64 >= 0.0
+        }
+        Some(WarpTarget::ClosestApproach(a, b)) => match bodies.get_many([a, b]) {
+            Ok([a, b]) => {
+                let distance = a.position.distance(b.position);
+                let receding = warp.last_distance.is_some_and(|last| distance > last);
+                if !receding && warp.last_distance.is_some() {
+                    warp.approaching = true;
+                }
+                warp.last_distance = Some(distance);
+                warp.approaching && receding
+            }
+            // One of them has merged or been removed.
+            Err(_) => true,
+        },
+    };
+    if reached {
+        if let Some(distance) = warp.last_distance {
+            info!(
+                "warp reached {:.3}, separation {:.3}",
+                clock.elapsed_seconds, distance
+            );
+        } else {
+            info!("warp reached {:.3}", clock.elapsed_seconds);
+        }
+        warp.stop(&mut universe);
+        simulation.send(SimulationCommand::Halt);
+    }
+}
+
+pub struct WarpPlugin;
+
+impl Plugin for WarpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WarpSettings>()
+            .init_resource::<Warp>()
+            .add_system(start_warp)
+            .add_system(drive_warp.after(start_warp))
+            .add_system(
+                check_warp
+                    .with_run_criteria(UniverseStep)
+                    .after(step_universe),
+            );
+    }
+}